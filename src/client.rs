@@ -0,0 +1,162 @@
+use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+const USER_AGENT: &str = "github.com/auno/adventofcode-2023 via ureq";
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct Client {
+    base_url: String,
+    session_token: String,
+    agent: ureq::Agent,
+    min_request_interval: Duration,
+    last_request: Cell<Option<Instant>>,
+}
+
+impl Client {
+    pub fn new(base_url: &str, session_token: &str) -> Self {
+        Client {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session_token: session_token.trim().to_string(),
+            agent: ureq::AgentBuilder::new().user_agent(USER_AGENT).build(),
+            min_request_interval: MIN_REQUEST_INTERVAL,
+            last_request: Cell::new(None),
+        }
+    }
+
+    pub fn with_min_request_interval(mut self, min_request_interval: Duration) -> Self {
+        self.min_request_interval = min_request_interval;
+        self
+    }
+
+    pub fn from_environment(base_url: Option<&str>) -> Result<Self> {
+        let base_url = match (base_url, env::var("AOC_BASE_URL")) {
+            (Some(base_url), _) => base_url.to_string(),
+            (None, Ok(base_url)) => base_url,
+            (None, Err(_)) => DEFAULT_BASE_URL.to_string(),
+        };
+
+        Ok(Client::new(&base_url, &session_token()?))
+    }
+
+    fn throttle(&self) {
+        if let Some(last_request) = self.last_request.get() {
+            let elapsed = last_request.elapsed();
+
+            if elapsed < self.min_request_interval {
+                sleep(self.min_request_interval - elapsed);
+            }
+        }
+
+        self.last_request.set(Some(Instant::now()));
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session_token)
+    }
+
+    pub fn get(&self, path: &str) -> Result<String> {
+        self.throttle();
+
+        match self.agent.get(&self.url(path)).set("Cookie", &self.cookie()).call() {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(status, response)) => {
+                bail!("GET {path} failed with status {status}: {}", response.into_string().unwrap_or_default().trim())
+            },
+            Err(e) => Err(e).context(format!("GET {path} failed")),
+        }
+    }
+
+    pub fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
+        self.throttle();
+
+        match self.agent.post(&self.url(path)).set("Cookie", &self.cookie()).send_form(form) {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(status, response)) => {
+                bail!("POST {path} failed with status {status}: {}", response.into_string().unwrap_or_default().trim())
+            },
+            Err(e) => Err(e).context(format!("POST {path} failed")),
+        }
+    }
+}
+
+fn config_file() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("aoc-2023").join("session"))
+}
+
+/// The session cookie, from `AOC_SESSION_TOKEN` or else from `~/.config/aoc-2023/session`.
+pub fn session_token() -> Result<String> {
+    if let Ok(token) = env::var("AOC_SESSION_TOKEN") {
+        if !token.trim().is_empty() {
+            return Ok(token);
+        }
+    }
+
+    let config_file = config_file().context("Missing AOC_SESSION_TOKEN and no config directory found")?;
+
+    fs::read_to_string(&config_file)
+        .map(|token| token.trim().to_string())
+        .context(format!("Missing AOC_SESSION_TOKEN and could not read {}", config_file.display()))
+}
+
+#[cfg(test)]
+pub mod stub {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    /// Serves the given `(status, body)` responses in order, one per connection, and hands back each raw request.
+    pub fn serve(responses: Vec<(u16, &'static str)>) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+
+                    request.push_str(&line);
+
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                request.push_str(&String::from_utf8(request_body).unwrap());
+                let _ = sender.send(request);
+
+                let response = format!("HTTP/1.1 {status} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+                reader.into_inner().write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (base_url, receiver)
+    }
+}
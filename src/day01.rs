@@ -1,17 +1,316 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
-use phf::phf_map;
-
-static DIGITS_SPELLED_OUT: phf::Map<&'static str, char> = phf_map! {
-    "one" => '1',
-    "two" => '2',
-    "three" => '3',
-    "four" => '4',
-    "five" => '5',
-    "six" => '6',
-    "seven" => '7',
-    "eight" => '8',
-    "nine" => '9',
-};
+use anyhow::{bail, Context, Error, Result};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+
+/// Spelled-out digits per language, indexed by value.
+const DIGITS_SPELLED_OUT: [(&str, [&str; 10]); 4] = [
+    ("en", ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"]),
+    ("de", ["null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun"]),
+    ("fr", ["zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf"]),
+    ("sv", ["noll", "ett", "två", "tre", "fyra", "fem", "sex", "sju", "åtta", "nio"]),
+];
+
+const NO_STATE: usize = usize::MAX;
+
+/// An Aho-Corasick automaton over bytes, with the failure links folded into a complete transition table.
+struct Automaton {
+    transitions: Vec<[usize; 256]>,
+    outputs: Vec<Vec<(usize, u32)>>,
+    max_len: usize,
+}
+
+impl Automaton {
+    fn new(patterns: &[(Vec<u8>, u32)]) -> Self {
+        let mut transitions = vec![[NO_STATE; 256]];
+        let mut outputs = vec![vec![]];
+
+        for (pattern, value) in patterns {
+            let mut state = 0;
+
+            for &b in pattern {
+                if transitions[state][b as usize] == NO_STATE {
+                    transitions.push([NO_STATE; 256]);
+                    outputs.push(vec![]);
+                    transitions[state][b as usize] = transitions.len() - 1;
+                }
+
+                state = transitions[state][b as usize];
+            }
+
+            outputs[state].push((pattern.len(), *value));
+        }
+
+        let mut fail = vec![0; transitions.len()];
+        let mut queue = VecDeque::new();
+
+        for transition in transitions[0].iter_mut() {
+            match *transition {
+                NO_STATE => { *transition = 0; },
+                child => { queue.push_back(child); },
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let inherited = outputs[fail[state]].clone();
+            outputs[state].extend(inherited);
+
+            let fallback = transitions[fail[state]];
+
+            for (transition, &fallback_transition) in transitions[state].iter_mut().zip(fallback.iter()) {
+                match *transition {
+                    NO_STATE => { *transition = fallback_transition; },
+                    child => {
+                        fail[child] = fallback_transition;
+                        queue.push_back(child);
+                    },
+                }
+            }
+        }
+
+        let max_len = patterns.iter().map(|(pattern, _)| pattern.len()).max().unwrap_or(0);
+
+        Automaton { transitions, outputs, max_len }
+    }
+
+    /// The match starting earliest in `haystack`, as `(start, len, value)`.
+    fn leftmost(&self, haystack: impl Iterator<Item = u8>) -> Option<(usize, usize, u32)> {
+        let mut state = 0;
+        let mut best: Option<(usize, usize, u32)> = None;
+
+        for (i, b) in haystack.enumerate() {
+            if matches!(best, Some((start, _, _)) if i >= start + self.max_len) {
+                break;
+            }
+
+            state = self.transitions[state][b as usize];
+
+            for &(len, value) in &self.outputs[state] {
+                let start = i + 1 - len;
+
+                if !matches!(best, Some((best_start, _, _)) if best_start <= start) {
+                    best = Some((start, len, value));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// The lowercase bytes of `line`, each with the byte range of the character in `line` it came from, since lowercasing
+/// may change the length of a character.
+fn lowercase_bytes(line: &str) -> impl DoubleEndedIterator<Item = (u8, Range<usize>)> + '_ {
+    line.char_indices().flat_map(|(i, c)| {
+        let range = i..(i + c.len_utf8());
+
+        c.to_lowercase().flat_map(move |lower| {
+            let range = range.clone();
+            let mut buffer = [0; 4];
+            let len = lower.encode_utf8(&mut buffer).len();
+
+            buffer.into_iter().take(len).map(move |b| (b, range.clone()))
+        })
+    })
+}
+
+/// Finds the first digit scanning forwards and the last digit scanning backwards with a reversed automaton.
+struct DigitFinder {
+    forward: Automaton,
+    backward: Automaton,
+    case_insensitive: bool,
+}
+
+impl DigitFinder {
+    fn new(patterns: &[(Vec<u8>, u32)], case_insensitive: bool) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|(pattern, value)| match case_insensitive {
+                true => (String::from_utf8_lossy(pattern).to_lowercase().into_bytes(), *value),
+                false => (pattern.clone(), *value),
+            })
+            .collect::<Vec<_>>();
+        let reversed_patterns = patterns
+            .iter()
+            .map(|(pattern, value)| (pattern.iter().rev().copied().collect(), *value))
+            .collect::<Vec<_>>();
+
+        DigitFinder {
+            forward: Automaton::new(&patterns),
+            backward: Automaton::new(&reversed_patterns),
+            case_insensitive,
+        }
+    }
+
+    fn digit_match(line: &str, range: Range<usize>, value: u32) -> DigitMatch {
+        let spelled_out = !line.as_bytes()[range.start].is_ascii_digit();
+        DigitMatch { value, range, spelled_out }
+    }
+
+    fn first_digit(&self, line: &str) -> Option<DigitMatch> {
+        match self.case_insensitive {
+            true => self.forward.leftmost(lowercase_bytes(line).map(|(b, _)| b)).map(|(start, len, value)| {
+                let char_range = |k| lowercase_bytes(line).nth(k).unwrap().1;
+                Self::digit_match(line, char_range(start).start..char_range(start + len - 1).end, value)
+            }),
+            false => self.forward
+                .leftmost(line.bytes())
+                .map(|(start, len, value)| Self::digit_match(line, start..(start + len), value)),
+        }
+    }
+
+    fn last_digit(&self, line: &str) -> Option<DigitMatch> {
+        let n = line.len();
+
+        match self.case_insensitive {
+            true => self.backward.leftmost(lowercase_bytes(line).rev().map(|(b, _)| b)).map(|(start, len, value)| {
+                let char_range = |k| lowercase_bytes(line).rev().nth(k).unwrap().1;
+                Self::digit_match(line, char_range(start + len - 1).start..char_range(start).end, value)
+            }),
+            false => self.backward
+                .leftmost(line.bytes().rev())
+                .map(|(start, len, value)| Self::digit_match(line, (n - start - len)..(n - start), value)),
+        }
+    }
+
+    fn report(&self, line_number: usize, line: &str) -> Result<LineReport> {
+        match (self.first_digit(line), self.last_digit(line)) {
+            (Some(first), Some(last)) => Ok(LineReport { line: line_number, value: first.value * 10 + last.value, first, last }),
+            _ => bail!("Line {line_number} has no digits: {line}"),
+        }
+    }
+}
+
+/// A digit found in a line, with its byte range within the line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DigitMatch {
+    pub value: u32,
+    pub range: Range<usize>,
+    pub spelled_out: bool,
+}
+
+/// How the calibration value of a single (1-based) line came about.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineReport {
+    pub line: usize,
+    pub first: DigitMatch,
+    pub last: DigitMatch,
+    pub value: u32,
+}
+
+fn digit_patterns() -> Vec<(Vec<u8>, u32)> {
+    (0..=9).map(|d| (d.to_string().into_bytes(), d)).collect()
+}
+
+/// Tokens that stand for a digit, on top of the digits themselves.
+pub struct Vocabulary {
+    tokens: Vec<(String, u32)>,
+    case_insensitive: bool,
+}
+
+impl Vocabulary {
+    /// Fails if a value is not a digit, or if a token (or a digit) is a prefix of another token, since a match could
+    /// then be read either way.
+    pub fn new(tokens: impl IntoIterator<Item = (String, u32)>, case_insensitive: bool) -> Result<Self> {
+        let tokens = tokens.into_iter().unique().collect_vec();
+        let fold = |token: &str| match case_insensitive {
+            true => token.to_lowercase(),
+            false => token.to_string(),
+        };
+
+        for (token, value) in &tokens {
+            if token.is_empty() {
+                bail!("Empty token");
+            }
+
+            if *value > 9 {
+                bail!("Invalid value for {token}: {value}; tokens must stand for a single digit");
+            }
+        }
+
+        let all = digit_patterns()
+            .into_iter()
+            .map(|(digit, value)| (String::from_utf8(digit).unwrap(), value))
+            .chain(tokens.iter().cloned())
+            .collect_vec();
+
+        for ((a, _), (b, _)) in all.iter().tuple_combinations() {
+            let (a, b) = (fold(a), fold(b));
+
+            if b.starts_with(&a) || a.starts_with(&b) {
+                bail!("Ambiguous tokens: {a} and {b}");
+            }
+        }
+
+        Ok(Vocabulary { tokens, case_insensitive })
+    }
+
+    pub fn builtin(language: &str, zero: bool, case_insensitive: bool) -> Result<Self> {
+        let (_, words) = DIGITS_SPELLED_OUT
+            .iter()
+            .find(|(l, _)| *l == language)
+            .context(format!("Unknown language: {language}"))?;
+
+        let tokens = words
+            .iter()
+            .enumerate()
+            .skip(if zero { 0 } else { 1 })
+            .map(|(value, word)| (word.to_string(), value as u32));
+
+        Vocabulary::new(tokens, case_insensitive)
+    }
+
+    pub fn english() -> Self {
+        Vocabulary::builtin("en", false, false).unwrap()
+    }
+
+    /// Reads `token = digit` lines; blank lines and lines starting with `#` are ignored.
+    pub fn load(path: &Path, case_insensitive: bool) -> Result<Self> {
+        let contents = fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+        let VocabularyFile(tokens) = contents.parse()?;
+
+        Vocabulary::new(tokens, case_insensitive)
+    }
+
+    fn digit_finder(&self) -> DigitFinder {
+        let patterns = digit_patterns()
+            .into_iter()
+            .chain(self.tokens.iter().map(|(token, value)| (token.as_bytes().to_vec(), *value)))
+            .collect_vec();
+
+        DigitFinder::new(&patterns, self.case_insensitive)
+    }
+}
+
+struct VocabularyFile(Vec<(String, u32)>);
+
+impl FromStr for VocabularyFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (token, value) = line.split_once('=').context(format!("Invalid vocabulary line: {line}"))?;
+                let value = value.trim().parse().context(format!("Invalid value: {line}"))?;
+
+                Ok((token.trim().to_string(), value))
+            })
+            .collect::<Result<_>>()
+            .map(VocabularyFile)
+    }
+}
+
+lazy_static! {
+    static ref DIGITS: DigitFinder = DigitFinder::new(&digit_patterns(), false);
+    static ref DIGITS_AND_SPELLED_OUT_DIGITS: DigitFinder = Vocabulary::english().digit_finder();
+}
 
 #[aoc_generator(day1)]
 fn parse(input: &str) -> Vec<String> {
@@ -21,41 +320,58 @@ fn parse(input: &str) -> Vec<String> {
         .collect()
 }
 
-fn solve(input: &[String], normalizer: fn(&String) -> String) -> u32 {
+fn solve(input: &[String], digit_finder: &DigitFinder) -> Result<u32> {
     input
         .iter()
-        .map(normalizer)
-        .map(|line| {
-            line.chars()
-                .filter(|c| c.is_ascii_digit())
-                .map(|c| c.to_digit(10).unwrap())
-                .collect::<Vec<u32>>()
-        })
-        .map(|digits| digits.first().unwrap() * 10 + digits.last().unwrap())
+        .enumerate()
+        .map(|(j, line)| digit_finder.report(j + 1, line).map(|report| report.value))
         .sum()
 }
 
 #[aoc(day1, part1)]
-fn part1(input: &[String]) -> u32 {
-    solve(input, String::to_owned)
+fn part1(input: &[String]) -> Result<u32> {
+    solve(input, &DIGITS)
 }
 
 #[aoc(day1, part2)]
-fn part2(input: &[String]) -> u32 {
-    let normalizer = |line: &String| {
-        let normalized_line = (0..line.len())
-            .map(|i| {
-                match DIGITS_SPELLED_OUT.entries().find(|(&dso, _)| line[i..].starts_with(dso)) {
-                    Some((_, &d)) => d,
-                    None => line[i..].chars().next().unwrap(),
-                }
-            })
-            .collect::<String>();
+fn part2(input: &[String]) -> Result<u32> {
+    solve(input, &DIGITS_AND_SPELLED_OUT_DIGITS)
+}
+
+/// Part 2 with digits spelled out according to `vocabulary`.
+pub fn calibrate(input: &str, vocabulary: &Vocabulary) -> Result<u32> {
+    solve(&parse(input), &vocabulary.digit_finder())
+}
 
-        normalized_line
-    };
+/// One report per line, or an error for each line without any digits.
+pub fn report(input: &str, vocabulary: &Vocabulary) -> Vec<Result<LineReport>> {
+    let digit_finder = vocabulary.digit_finder();
 
-    solve(input, normalizer)
+    input
+        .lines()
+        .enumerate()
+        .map(|(j, line)| digit_finder.report(j + 1, line))
+        .collect()
+}
+
+fn describe(line: &str, digit: &DigitMatch) -> String {
+    let kind = if digit.spelled_out { "spelled out" } else { "digit" };
+    format!("`{}` = {} at {}..{} ({kind})", &line[digit.range.clone()], digit.value, digit.range.start, digit.range.end)
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let lines = input.lines().collect_vec();
+
+    report(input, &Vocabulary::english())
+        .into_iter()
+        .map(|report| {
+            let report = report?;
+            let line = lines[report.line - 1];
+
+            Ok(format!("{}: {line}: first {}, last {} => {}", report.line, describe(line, &report.first), describe(line, &report.last), report.value))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|lines| lines.join("\n"))
 }
 
 #[cfg(test)]
@@ -64,27 +380,75 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(142, part1(&parse(include_str!("../test_input/day01.part1.142.txt"))));
+        assert_eq!(142, part1(&parse(include_str!("../test_input/day01.part1.142.txt"))).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(53921, part1(&parse(include_str!("../input/2023/day1.txt"))));
+        assert_eq!(53921, part1(&parse(include_str!("../input/2023/day1.txt"))).unwrap());
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(281, part2(&parse(include_str!("../test_input/day01.part2.281.txt"))));
+        assert_eq!(281, part2(&parse(include_str!("../test_input/day01.part2.281.txt"))).unwrap());
     }
 
     #[test]
     fn part2_overlapping() {
-        assert_eq!(82, part2(&parse("eightwo")));
-        assert_eq!(98, part2(&parse("nineight")));
+        assert_eq!(82, part2(&parse("eightwo")).unwrap());
+        assert_eq!(98, part2(&parse("nineight")).unwrap());
+        assert_eq!(21, part2(&parse("twone")).unwrap());
+        assert_eq!(33, part2(&parse("threeeee")).unwrap());
+        assert_eq!(18, part2(&parse("oneight")).unwrap());
+    }
+
+    #[test]
+    fn builtin_vocabularies() {
+        let german = Vocabulary::builtin("de", true, true).unwrap();
+        assert_eq!(70 + 13, calibrate("abSiebenzwei0x\nEINSdrei", &german).unwrap());
+        assert!(Vocabulary::builtin("xx", false, false).is_err());
+
+        assert_eq!(55, calibrate("FÜNF", &german).unwrap());
+        assert!(calibrate("FÜNF", &Vocabulary::builtin("de", false, false).unwrap()).is_err());
+        let swedish = Vocabulary::builtin("sv", false, true).unwrap();
+        let reports = report("xÅTTAtVÅ", &swedish);
+        let line = reports[0].as_ref().unwrap();
+        assert_eq!((1..6, 6..10, 82), (line.first.range.clone(), line.last.range.clone(), line.value));
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let VocabularyFile(tokens) = "# roman\nI = 1\nV = 5\nX = 0\n".parse().unwrap();
+        let roman = Vocabulary::new(tokens, false).unwrap();
+        assert_eq!(50, calibrate("aVbIcX", &roman).unwrap());
+        assert_eq!(11, calibrate("aVbIcXi", &Vocabulary::new([("I".to_string(), 1)], false).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn ambiguous_vocabulary() {
+        assert!(Vocabulary::new([("I".to_string(), 1), ("II".to_string(), 2)], false).is_err());
+        assert!(Vocabulary::new([("one".to_string(), 1), ("ONEe".to_string(), 3)], true).is_err());
+        assert!(Vocabulary::new([("one".to_string(), 1), ("ONEe".to_string(), 3)], false).is_ok());
+        assert!(Vocabulary::new([("1st".to_string(), 1)], false).is_err());
+        assert!(Vocabulary::new([("ten".to_string(), 10)], false).is_err());
+    }
+
+    #[test]
+    fn report_positions() {
+        let reports = report("xtwone3four\nabc\n7pqr", &Vocabulary::english());
+
+        let first = reports[0].as_ref().unwrap();
+        assert_eq!(DigitMatch { value: 2, range: 1..4, spelled_out: true }, first.first);
+        assert_eq!(DigitMatch { value: 4, range: 7..11, spelled_out: true }, first.last);
+        assert_eq!(24, first.value);
+        assert!(reports[1].is_err());
+        let third = reports[2].as_ref().unwrap();
+        assert_eq!((3, 0..1, false, 77), (third.line, third.first.range.clone(), third.last.spelled_out, third.value));
+        assert!(part1(&parse("1a\nb")).is_err());
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(54676, part2(&parse(include_str!("../input/2023/day1.txt"))));
+        assert_eq!(54676, part2(&parse(include_str!("../input/2023/day1.txt"))).unwrap());
     }
 }
@@ -1,64 +1,239 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::{Context, Result, bail};
-use scan_fmt::scan_fmt;
-use std::cmp::max;
+use anyhow::{bail, Context, Error, Result};
 use itertools::Itertools;
+use crate::parser::{key_value, Span};
 
-type Reveal = (u32, u32, u32);
-type Game = (u32, Vec<Reveal>);
-
-fn parse_reveal(reveal: &str) -> Result<Reveal> {
-    reveal
-        .split(", ")
-        .map(|count_color| {
-            let (count, color) = scan_fmt!(count_color, "{d} {}", u32, String)?;
-            Ok(
-                match color.as_str() {
-                    "red" => (count, 0, 0),
-                    "green" => (0, count, 0),
-                    "blue" => (0, 0, count),
-                    _ => bail!("Unknown color: {}", color),
-                }
-            )
-        })
-        .fold_ok((0, 0, 0), |(r1, g1, b1), (r2, g2, b2)| (r1 + r2, g1 + g2, b1 + b2))
+/// Cube counts per color, as seen in a reveal or as available in a bag.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Cubes(BTreeMap<String, u32>);
+
+impl Cubes {
+    pub fn count(&self, color: &str) -> u32 {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.0.iter().map(|(color, &count)| (color.as_str(), count))
+    }
+
+    /// Whether every cube in `self` could have been drawn from `bag`.
+    pub fn fits_in(&self, bag: &Cubes) -> bool {
+        self.colors().all(|(color, count)| count <= bag.count(color))
+    }
+
+    fn add(&mut self, color: &str, count: u32) {
+        *self.0.entry(color.to_string()).or_default() += count;
+    }
+
+    fn union(mut self, other: &Cubes) -> Cubes {
+        for (color, count) in other.colors() {
+            let max = self.0.entry(color.to_string()).or_default();
+            *max = (*max).max(count);
+        }
+
+        self
+    }
+}
+
+impl Display for Cubes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.colors().map(|(color, count)| format!("{color}={count}")).join(","))
+    }
+}
+
+/// A bag such as `red=12,green=13,blue=14`.
+impl FromStr for Cubes {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut cubes = Cubes::default();
+
+        for color_count in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (color, count) = color_count.split_once('=').context(format!("Expected `color=count`: {color_count}"))?;
+            cubes.add(color.trim(), count.trim().parse().context(format!("Invalid count: {color_count}"))?);
+        }
+
+        Ok(cubes)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub id: u32,
+    pub reveals: Vec<Cubes>,
+}
+
+impl Game {
+    /// The first reveal (and its index) that could not have been drawn from `bag`.
+    pub fn infeasible_reveal(&self, bag: &Cubes) -> Option<(usize, &Cubes)> {
+        self.reveals.iter().find_position(|reveal| !reveal.fits_in(bag))
+    }
+
+    pub fn is_feasible(&self, bag: &Cubes) -> bool {
+        self.infeasible_reveal(bag).is_none()
+    }
+
+    /// The fewest cubes of each color that make every reveal feasible.
+    pub fn minimal_bag(&self) -> Cubes {
+        self.reveals.iter().fold(Cubes::default(), Cubes::union)
+    }
+}
+
+fn parse_reveal(reveal: Span) -> Result<Cubes> {
+    let mut cubes = Cubes::default();
+
+    for count_color in reveal.split(", ") {
+        let (count, color) = count_color.split_once(" ")?;
+        cubes.add(color.as_str(), count.parse()?);
+    }
+
+    Ok(cubes)
 }
 
 #[aoc_generator(day2)]
-fn parse(input: &str) -> Result<Vec<Game>> {
-    input
+pub fn parse(input: &str) -> Result<Vec<Game>> {
+    Span::new(input)
         .lines()
         .map(|line| {
-            let (game, reveals) = line.split_once(": ").context(format!("Unable to parse line: {}", line))?;
-            let game_id = scan_fmt!(game, "Game {d}", u32)?;
+            let (game, reveals) = key_value(line)?;
+            let id = game.strip_prefix("Game ")?.parse()?;
             let reveals = reveals
                 .split("; ")
                 .map(parse_reveal)
-                .collect::<Result<Vec<Reveal>>>()?;
+                .collect::<Result<Vec<Cubes>>>()?;
 
-            Ok((game_id, reveals))
+            Ok(Game { id, reveals })
         })
         .collect()
 }
 
-#[aoc(day2, part1)]
-fn part1(games: &[Game]) -> u32 {
+/// The sum of the IDs of the games that are feasible with `bag`.
+pub fn feasible_game_ids(games: &[Game], bag: &Cubes) -> u32 {
     games
         .iter()
-        .filter(|(_, reveals)| reveals.iter().all(|&(r, g, b)| r <= 12 && g <= 13 && b <= 14))
-        .map(|(game_id, _)| game_id)
+        .filter(|game| game.is_feasible(bag))
+        .map(|game| game.id)
         .sum()
 }
 
+/// The counts of a single color that a bag may hold, with `max: None` meaning unbounded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ColorRange {
+    pub min: u32,
+    pub max: Option<u32>,
+}
+
+impl ColorRange {
+    fn contains(&self, other: &ColorRange) -> bool {
+        self.min <= other.min && match (self.max, other.max) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => a >= b,
+        }
+    }
+}
+
+impl Display for ColorRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) => write!(f, "{}..={max}", self.min),
+            None => write!(f, "{}..", self.min),
+        }
+    }
+}
+
+/// A box of bags: every combination of counts within the per-color ranges.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BagRanges(pub BTreeMap<String, ColorRange>);
+
+impl BagRanges {
+    fn contains(&self, other: &BagRanges) -> bool {
+        self.0.iter().all(|(color, range)| range.contains(&other.0[color]))
+    }
+
+    /// Every bag in here that fits fewer than `required` cubes of `color`.
+    fn below(&self, color: &str, required: u32) -> Option<BagRanges> {
+        let mut below = self.clone();
+        let range = below.0.get_mut(color)?;
+        let fewer = required.checked_sub(1)?;
+        let max = range.max.map_or(fewer, |max| max.min(fewer));
+
+        if max < range.min {
+            return None;
+        }
+
+        range.max = Some(max);
+        Some(below)
+    }
+}
+
+impl Display for BagRanges {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.iter().map(|(color, range)| format!("{color}={range}")).join(","))
+    }
+}
+
+/// Every bag consistent with the given games being possible and impossible, as a union of [`BagRanges`]. Games that
+/// are in neither list are ignored, and colors that none of the listed games mention are left out.
+pub fn infer_bags(games: &[Game], possible: &[u32], impossible: &[u32]) -> Result<Vec<BagRanges>> {
+    let minimal_bags = |ids: &[u32]| {
+        ids.iter()
+            .map(|&id| match games.iter().find(|game| game.id == id) {
+                Some(game) => Ok((id, game.minimal_bag())),
+                None => bail!("Unknown game: {id}"),
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+
+    if let Some(id) = possible.iter().find(|id| impossible.contains(id)) {
+        bail!("Game {id} is marked both possible and impossible");
+    }
+
+    let possible = minimal_bags(possible)?;
+    let impossible = minimal_bags(impossible)?;
+    let required = possible.iter().fold(Cubes::default(), |required, (_, bag)| required.union(bag));
+
+    let colors = possible.iter().chain(&impossible).flat_map(|(_, bag)| bag.colors().map(|(color, _)| color)).unique();
+    let mut boxes = vec![BagRanges(
+        colors.map(|color| (color.to_string(), ColorRange { min: required.count(color), max: None })).collect()
+    )];
+
+    for (id, bag) in &impossible {
+        let next = boxes
+            .iter()
+            .flat_map(|bag_ranges| bag.colors().filter_map(|(color, count)| bag_ranges.below(color, count)))
+            .collect_vec();
+
+        if next.is_empty() {
+            bail!("Contradictory constraints: game {id} is marked impossible, but the possible games need at least {required}");
+        }
+
+        boxes = next
+            .iter()
+            .enumerate()
+            .filter(|&(i, b)| !next.iter().enumerate().any(|(j, a)| i != j && a.contains(b) && (!b.contains(a) || j < i)))
+            .map(|(_, b)| b.clone())
+            .collect();
+    }
+
+    Ok(boxes)
+}
+
+#[aoc(day2, part1)]
+fn part1(games: &[Game]) -> u32 {
+    feasible_game_ids(games, &"red=12,green=13,blue=14".parse().unwrap())
+}
+
 #[aoc(day2, part2)]
 fn part2(games: &[Game]) -> u32 {
     games
         .iter()
-        .map(|(_, reveals)| {
-            let (r, g, b) = reveals
-                .iter()
-                .fold((0, 0, 0), |(r1, g1, b1), &(r2, g2, b2)| (max(r1, r2), max(g1, g2), max(b1, b2)));
-            r * g * b
+        .map(|game| {
+            let bag = game.minimal_bag();
+            ["red", "green", "blue"].iter().map(|color| bag.count(color)).product::<u32>()
         })
         .sum()
 }
@@ -77,6 +252,36 @@ mod tests {
         assert_eq!(2679, part1(&parse(include_str!("../input/2023/day2.txt")).unwrap()));
     }
 
+    #[test]
+    fn other_colors() {
+        let games = parse("Game 1: 3 blue, 2 yellow; 1 red\nGame 2: 6 yellow, 1 blue").unwrap();
+        let bag = "red=12,green=13,blue=14,yellow=5".parse::<Cubes>().unwrap();
+
+        assert!(games[0].is_feasible(&bag));
+        assert_eq!(Some((0, &games[1].reveals[0])), games[1].infeasible_reveal(&bag));
+        assert_eq!("blue=3,red=1,yellow=2", games[0].minimal_bag().to_string());
+        assert_eq!(1, feasible_game_ids(&games, &bag));
+        assert!("red=twelve".parse::<Cubes>().is_err());
+    }
+
+    #[test]
+    fn inferred_bags() {
+        let games = parse(include_str!("../test_input/day02.part1.8.txt")).unwrap();
+
+        let bags = infer_bags(&games, &[1, 2, 5], &[3, 4]).unwrap();
+        assert_eq!(
+            vec!["blue=6..=14,green=3..=12,red=6..", "blue=6..=14,green=3..,red=6..=19", "blue=6..,green=3..,red=6..=13"],
+            bags.iter().map(|bag| bag.to_string()).collect_vec(),
+        );
+
+        assert!(infer_bags(&games, &[3], &[1]).is_err());
+        assert!(infer_bags(&games, &[1], &[1]).is_err());
+        assert!(infer_bags(&games, &[6], &[]).is_err());
+
+        let games = parse("Game 1: 1 red\nGame 2: 0 blue, 1 red").unwrap();
+        assert!(infer_bags(&games, &[1], &[2]).is_err());
+    }
+
     #[test]
     fn part2_example1() {
         assert_eq!(2286, part2(&parse(include_str!("../test_input/day02.part2.2286.txt")).unwrap()));
@@ -1,97 +1,308 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use aoc_runner_derive::{aoc, aoc_generator};
+use anyhow::{bail, Result};
 use itertools::Itertools;
-use scan_fmt::scan_fmt;
+use crate::Format;
+use crate::parser::Span;
 
-type Schematic = (HashMap<(isize, isize), ((isize, isize), u32, usize)>, HashMap<(isize, isize), char>);
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PartNumber {
+    pub value: u32,
+    pub row: usize,
+    pub col_range: Range<usize>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Symbol {
+    pub char: char,
+    pub pos: (usize, usize),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Combine {
+    Product,
+    Sum,
+}
+
+/// Which symbols count as gears, and how their neighboring numbers make up the gear ratio.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GearRule {
+    /// `None` for any symbol.
+    pub symbol: Option<char>,
+    pub neighbors: usize,
+    pub combine: Combine,
+}
+
+impl GearRule {
+    pub const STANDARD: GearRule = GearRule { symbol: Some('*'), neighbors: 2, combine: Combine::Product };
+}
+
+/// Numbers and symbols, with the adjacency between them precomputed both ways (by index).
+#[derive(Clone, Debug)]
+pub struct Schematic {
+    pub rows: usize,
+    pub cols: usize,
+    pub numbers: Vec<PartNumber>,
+    pub symbols: Vec<Symbol>,
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    fn new(rows: usize, cols: usize, numbers: Vec<PartNumber>, symbols: Vec<Symbol>) -> Self {
+        let symbol_indices: HashMap<_, _> = symbols.iter().enumerate().map(|(s, symbol)| (symbol.pos, s)).collect();
+        let mut number_symbols = vec![vec![]; numbers.len()];
+        let mut symbol_numbers = vec![vec![]; symbols.len()];
+
+        for (n, number) in numbers.iter().enumerate() {
+            for j in number.row.saturating_sub(1)..=(number.row + 1) {
+                for i in number.col_range.start.saturating_sub(1)..=number.col_range.end {
+                    if let Some(&s) = symbol_indices.get(&(j, i)) {
+                        number_symbols[n].push(s);
+                        symbol_numbers[s].push(n);
+                    }
+                }
+            }
+        }
+
+        Schematic { rows, cols, numbers, symbols, number_symbols, symbol_numbers }
+    }
+
+    pub fn adjacent_numbers(&self, symbol: usize) -> impl Iterator<Item = &PartNumber> {
+        self.symbol_numbers[symbol].iter().map(|&n| &self.numbers[n])
+    }
+
+    pub fn adjacent_symbols(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_symbols[number].iter().map(|&s| &self.symbols[s])
+    }
+
+    /// Numbers adjacent to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers.iter().enumerate().filter(|&(n, _)| !self.number_symbols[n].is_empty()).map(|(_, number)| number)
+    }
+
+    /// Numbers adjacent to no symbol at all.
+    pub fn isolated_numbers(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers.iter().enumerate().filter(|&(n, _)| self.number_symbols[n].is_empty()).map(|(_, number)| number)
+    }
+
+    /// The symbols that are gears according to `rule`, together with their gear ratios, or an error for a gear whose
+    /// ratio overflows.
+    pub fn gears<'a>(&'a self, rule: &'a GearRule) -> impl Iterator<Item = Result<(&'a Symbol, u64)>> + 'a {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|&(s, symbol)| {
+                (rule.symbol.is_none() || rule.symbol == Some(symbol.char)) && self.symbol_numbers[s].len() == rule.neighbors
+            })
+            .map(|(s, symbol)| {
+                let mut values = self.adjacent_numbers(s).map(|number| number.value as u64);
+                let ratio = match rule.combine {
+                    Combine::Product => values.try_fold(1, u64::checked_mul),
+                    Combine::Sum => values.try_fold(0, u64::checked_add),
+                };
+
+                match ratio {
+                    Some(ratio) => Ok((symbol, ratio)),
+                    None => bail!("The gear ratio at {:?} overflows", symbol.pos),
+                }
+            })
+    }
+}
 
 #[aoc_generator(day3)]
-fn parse(input: &str) -> Schematic {
-    let mut numbers = HashMap::new();
-    let mut symbols = HashMap::new();
+pub fn parse(input: &str) -> Result<Schematic> {
+    let mut numbers = vec![];
+    let mut symbols = vec![];
+    let mut rows = 0;
+    let mut cols = 0;
 
-    for (j, line) in input.lines().enumerate() {
-        let mut i = 0;
+    for (j, line) in Span::new(input).lines().enumerate() {
+        rows = j + 1;
+        cols = cols.max(line.as_str().chars().count());
+        let mut chars = line.chars().enumerate().peekable();
 
-        while i < line.len() {
-            match line.chars().nth(i).unwrap() {
-                '.' => {
-                    i += 1;
-                },
+        while let Some((i, (c, cell))) = chars.next() {
+            match c {
+                '.' => {},
                 '0'..='9' => {
-                    let number = scan_fmt!(&line[i..], "{d}", String).unwrap();
-                    let num_digits = number.len();
-                    let number = number.parse::<u32>().unwrap();
+                    let mut num_digits = 1;
 
-                    for k in 0..(num_digits) {
-                        numbers.insert((j as isize, (i+ k) as isize), ((j as isize, i as isize), number, num_digits));
+                    while chars.next_if(|(_, (c, _))| c.is_ascii_digit()).is_some() {
+                        num_digits += 1;
                     }
 
-                    i += num_digits;
+                    let value = line.as_str()[i..(i + num_digits)].parse::<u32>().map_err(|e| cell.error(e))?;
+                    numbers.push(PartNumber { value, row: j, col_range: i..(i + num_digits) });
                 },
                 symbol => {
-                    symbols.insert((j as isize, i as isize), symbol);
-                    i += 1;
+                    symbols.push(Symbol { char: symbol, pos: (j, i) });
                 },
             }
         }
     }
 
-    (numbers, symbols)
+    Ok(Schematic::new(rows, cols, numbers, symbols))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Highlight {
+    Plain,
+    PartNumber,
+    IsolatedNumber,
+    Gear(u64),
+    Symbol,
+}
+
+impl Highlight {
+    fn ansi(&self) -> &'static str {
+        match self {
+            Highlight::Plain => "\x1b[0;2m",
+            Highlight::PartNumber => "\x1b[0;32m",
+            Highlight::IsolatedNumber => "\x1b[0;31m",
+            Highlight::Gear(_) => "\x1b[0;1;33m",
+            Highlight::Symbol => "\x1b[0;36m",
+        }
+    }
+
+    fn class(&self) -> &'static str {
+        match self {
+            Highlight::Plain => "plain",
+            Highlight::PartNumber => "part",
+            Highlight::IsolatedNumber => "isolated",
+            Highlight::Gear(_) => "gear",
+            Highlight::Symbol => "symbol",
+        }
+    }
+}
+
+/// Every cell of the schematic with its highlight, row by row.
+fn highlighted_cells(schematic: &Schematic, rule: &GearRule) -> Result<Vec<Vec<(char, Highlight)>>> {
+    let mut cells = vec![vec![('.', Highlight::Plain); schematic.cols]; schematic.rows];
+    let gear_ratios: HashMap<_, _> = schematic.gears(rule).map_ok(|(symbol, ratio)| (symbol.pos, ratio)).collect::<Result<_>>()?;
+
+    for (n, number) in schematic.numbers.iter().enumerate() {
+        let highlight = match schematic.number_symbols[n].is_empty() {
+            true => Highlight::IsolatedNumber,
+            false => Highlight::PartNumber,
+        };
+        let digits = format!("{:0width$}", number.value, width = number.col_range.len());
+
+        for (i, digit) in number.col_range.clone().zip(digits.chars()) {
+            cells[number.row][i] = (digit, highlight);
+        }
+    }
+
+    for symbol in &schematic.symbols {
+        let (j, i) = symbol.pos;
+        cells[j][i] = match gear_ratios.get(&symbol.pos) {
+            Some(&ratio) => (symbol.char, Highlight::Gear(ratio)),
+            None => (symbol.char, Highlight::Symbol),
+        };
+    }
+
+    Ok(cells)
+}
+
+/// The schematic with ANSI colors: part numbers green, isolated numbers red, gears yellow and other symbols cyan.
+pub fn render_ansi(schematic: &Schematic, rule: &GearRule) -> Result<String> {
+    let mut output = String::new();
+
+    for row in highlighted_cells(schematic, rule)? {
+        let mut previous = None;
+
+        for (c, highlight) in row {
+            if previous != Some(highlight.ansi()) {
+                output.push_str(highlight.ansi());
+                previous = Some(highlight.ansi());
+            }
+
+            output.push(c);
+        }
+
+        output.push_str("\x1b[0m\n");
+    }
+
+    Ok(output)
 }
 
-fn neighbors(j: isize, i: isize) -> [(isize, isize); 8] {
-    [
-        (j, i - 1),
-        (j, i + 1),
-        (j - 1, i),
-        (j + 1, i),
-        (j - 1, i - 1),
-        (j - 1, i + 1),
-        (j + 1, i - 1),
-        (j + 1, i + 1)
-    ]
+fn escape_html(c: char) -> String {
+    match c {
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '&' => "&amp;".to_string(),
+        '"' => "&quot;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+/// A standalone HTML page with the same highlighting as [`render_ansi`], where each gear shows its ratio on hover.
+pub fn render_html(schematic: &Schematic, rule: &GearRule) -> Result<String> {
+    let mut body = String::new();
+
+    for row in highlighted_cells(schematic, rule)? {
+        for (highlight, cells) in &row.into_iter().group_by(|&(_, highlight)| highlight) {
+            let text = cells.map(|(c, _)| escape_html(c)).collect::<String>();
+
+            match highlight {
+                Highlight::Gear(ratio) => body.push_str(&format!(
+                    r#"<span class="gear" data-ratio="{ratio}" title="gear ratio {ratio}">{text}</span>"#
+                )),
+                highlight => body.push_str(&format!(r#"<span class="{}">{text}</span>"#, highlight.class())),
+            }
+        }
+
+        body.push('\n');
+    }
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Gear Ratios</title>
+<style>
+body {{ background: #0f0f23; color: #cccccc; }}
+.plain {{ color: #555555; }}
+.part {{ color: #00cc00; }}
+.isolated {{ color: #ff4444; }}
+.gear {{ color: #ffff66; font-weight: bold; }}
+.symbol {{ color: #00cccc; }}
+</style>
+</head>
+<body>
+<pre>
+{body}</pre>
+</body>
+</html>
+"#
+    ))
+}
+
+pub(crate) fn render(input: &str, format: Format) -> Result<String> {
+    let schematic = parse(input)?;
+
+    match format {
+        Format::Ansi => render_ansi(&schematic, &GearRule::STANDARD),
+        Format::Html => render_html(&schematic, &GearRule::STANDARD),
+        Format::Svg => bail!("Day 3 cannot be rendered as SVG"),
+    }
 }
 
 #[aoc(day3, part1)]
 fn part1(schematic: &Schematic) -> u32 {
-    let (numbers, symbols) = schematic;
-
-    numbers
-        .values()
-        .unique()
-        .filter(|((j, i), _, num_digits)| {
-            (0..(*num_digits as isize))
-                .flat_map(|k| neighbors(*j, i + k))
-                .any(|neighbor| symbols.contains_key(&neighbor))
-        })
-        .map(|(_, number, _)| number)
+    schematic
+        .part_numbers()
+        .map(|number| number.value)
         .sum()
 }
 
 #[aoc(day3, part2)]
-fn part2(schematic: &Schematic) -> u32 {
-    let (numbers, symbols) = schematic;
-
-    symbols
-        .iter()
-        .filter(|&(_, symbol)| *symbol == '*')
-        .map(|((j, i), _)| {
-            let neighboring_numbers = neighbors(*j, *i)
-                .iter()
-                .filter_map(|neighbor| numbers.get(neighbor))
-                .unique()
-                .collect_vec();
-
-            if neighboring_numbers.len() != 2 {
-                return 0;
-            }
-
-            neighboring_numbers
-                .iter()
-                .map(|(_, number, _)| number)
-                .product::<u32>()
-        })
+fn part2(schematic: &Schematic) -> Result<u64> {
+    schematic
+        .gears(&GearRule::STANDARD)
+        .map_ok(|(_, ratio)| ratio)
         .sum()
 }
 
@@ -101,21 +312,53 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(4361, part1(&parse(include_str!("../test_input/day03.part1.4361.txt"))));
+        assert_eq!(4361, part1(&parse(include_str!("../test_input/day03.part1.4361.txt")).unwrap()));
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(539713, part1(&parse(include_str!("../input/2023/day3.txt"))));
+        assert_eq!(539713, part1(&parse(include_str!("../input/2023/day3.txt")).unwrap()));
+    }
+
+    #[test]
+    fn adjacency_queries() {
+        let schematic = parse(include_str!("../test_input/day03.part1.4361.txt")).unwrap();
+        let star = schematic.symbols.iter().position(|symbol| symbol.pos == (1, 3)).unwrap();
+
+        assert_eq!(vec![467, 35], schematic.adjacent_numbers(star).map(|number| number.value).collect::<Vec<_>>());
+        assert_eq!(vec![114, 58], schematic.isolated_numbers().map(|number| number.value).collect::<Vec<_>>());
+        assert_eq!(2, schematic.gears(&GearRule::STANDARD).count());
+
+        let rule = GearRule { symbol: None, neighbors: 1, combine: Combine::Sum };
+        assert_eq!(633 + 617 + 592 + 664, schematic.gears(&rule).map_ok(|(_, ratio)| ratio).sum::<Result<u64>>().unwrap());
+
+        let schematic = parse("999.999\n...*...\n999.999").unwrap();
+        let rule = GearRule { neighbors: 4, ..GearRule::STANDARD };
+        assert_eq!(vec![996_005_996_001], schematic.gears(&rule).map_ok(|(_, ratio)| ratio).collect::<Result<Vec<_>>>().unwrap());
+
+        let schematic = parse("99999.99999\n.....*.....\n99999.99999").unwrap();
+        assert!(schematic.gears(&rule).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn render_highlights() {
+        let schematic = parse("467..\n...*.\n..35.\n.....\n1.2.&").unwrap();
+        let ansi = render_ansi(&schematic, &GearRule::STANDARD).unwrap();
+        let html = render_html(&schematic, &GearRule::STANDARD).unwrap();
+
+        assert!(ansi.starts_with("\x1b[0;32m467\x1b[0;2m..\x1b[0m\n\x1b[0;2m...\x1b[0;1;33m*\x1b[0;2m.\x1b[0m\n"));
+        assert!(html.contains(r#"<span class="gear" data-ratio="16345" title="gear ratio 16345">*</span>"#));
+        assert!(html.contains(r#"<span class="isolated">1</span><span class="plain">.</span><span class="isolated">2</span>"#));
+        assert!(html.contains(r#"<span class="symbol">&amp;</span>"#));
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(467835, part2(&parse(include_str!("../test_input/day03.part2.467835.txt"))));
+        assert_eq!(467835, part2(&parse(include_str!("../test_input/day03.part2.467835.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(84159075, part2(&parse(include_str!("../input/2023/day3.txt"))));
+        assert_eq!(84159075, part2(&parse(include_str!("../input/2023/day3.txt")).unwrap()).unwrap());
     }
 }
@@ -1,10 +1,13 @@
+use std::cmp::Reverse;
 use std::collections::HashSet;
+use std::iter::once;
 use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::{Result, Error, Context};
+use anyhow::{bail, Result, Error, Context};
+use itertools::Itertools;
 
 #[derive(Clone)]
-struct Card {
+pub struct Card {
     winning_numbers: HashSet<u32>,
     numbers: HashSet<u32>,
 }
@@ -38,34 +41,175 @@ impl FromStr for Card {
 }
 
 impl Card {
-    fn count_winners(&self) -> usize {
+    pub fn count_winners(&self) -> usize {
         self.winning_numbers.intersection(&self.numbers).count()
     }
 }
 
 #[aoc_generator(day4)]
-fn parse(input: &str) -> Result<Vec<Card>> {
+pub fn parse(input: &str) -> Result<Vec<Card>> {
     input.lines().map(str::parse).collect()
 }
 
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Points {
+    /// 1, 2, 4, 8, ... points.
+    #[default]
+    Exponential,
+    /// 1, 2, 3, 4, ... points.
+    Linear,
+    /// 1, 1, 2, 3, 5, ... points.
+    Fibonacci,
+}
+
+impl Points {
+    /// Fails if the score does not fit in a `u64`.
+    pub fn score(&self, winners: usize) -> Result<u64> {
+        let score = match (self, winners) {
+            (Points::Exponential, 0) => Some(0),
+            (Points::Exponential, n) => u32::try_from(n - 1).ok().and_then(|shift| 1u64.checked_shl(shift)),
+            (Points::Linear, n) => Some(n as u64),
+            (Points::Fibonacci, n) => (0..n)
+                .try_fold((0u64, Some(1u64)), |(a, b), _| b.map(|b| (b, a.checked_add(b))))
+                .map(|(a, _)| a),
+        };
+
+        score.context(format!("The score for {winners} winners overflows"))
+    }
+}
+
+impl FromStr for Points {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "exponential" => Ok(Points::Exponential),
+            "linear" => Ok(Points::Linear),
+            "fibonacci" => Ok(Points::Fibonacci),
+            _ => bail!("Unknown points scheme: {s}"),
+        }
+    }
+}
+
+/// Each winning number wins a copy of `multiplier` instances of each of the next `reach` cards.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Copies {
+    pub reach: usize,
+    pub multiplier: u64,
+}
+
+impl Default for Copies {
+    fn default() -> Self {
+        Copies { reach: 1, multiplier: 1 }
+    }
+}
+
+/// How cards are scored: points for part 1 and copies for part 2.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScoringRule {
+    pub points: Points,
+    pub copies: Copies,
+}
+
+impl ScoringRule {
+    pub fn total_points(&self, cards: &[Card]) -> Result<u64> {
+        cards
+            .iter()
+            .try_fold(0u64, |total, card| {
+                total.checked_add(self.points.score(card.count_winners())?).context("The total points overflow")
+            })
+    }
+
+    /// The number of cards ending up in the pile, copies won past the last card being lost.
+    pub fn total_cards(&self, cards: &[Card]) -> u64 {
+        let mut counts = vec![0; cards.len()];
+
+        for i in (0..cards.len()).rev() {
+            let reach = (cards[i].count_winners() * self.copies.reach).min(cards.len() - 1 - i);
+            counts[i] = 1 + self.copies.multiplier * (1..=reach).map(|j| counts[i + j]).sum::<u64>();
+        }
+
+        counts.iter().sum()
+    }
+}
+
 #[aoc(day4, part1)]
-fn part1(cards: &[Card]) -> u32 {
-    cards
-        .iter()
-        .map(|card| card.count_winners() as u32)
-        .map(|num_winners| 2u32.pow(num_winners) >> 1)
-        .sum()
+fn part1(cards: &[Card]) -> Result<u64> {
+    ScoringRule::default().total_points(cards)
 }
 
 #[aoc(day4, part2)]
-fn part2(cards: &[Card]) -> u32 {
-    let mut counts = vec![0; cards.len()];
+fn part2(cards: &[Card]) -> u64 {
+    ScoringRule::default().total_cards(cards)
+}
+
+/// A forward simulation of part 2, processing the cards in order and tracking where every copy came from.
+pub struct Cascade {
+    /// The number of instances of each card, initially and after each card has been processed.
+    pub steps: Vec<Vec<u32>>,
+    /// `origins[k][o]`: how many instances of card `k` descend from original card `o` (including the original itself).
+    pub origins: Vec<Vec<u32>>,
+}
 
-    for i in (0..cards.len()).rev() {
-        counts[i] = 1 + (1..=cards[i].count_winners()).map(|j| counts[i + j]).sum::<u32>();
+impl Cascade {
+    pub fn simulate(cards: &[Card]) -> Self {
+        let n = cards.len();
+        let mut origins = (0..n).map(|k| (0..n).map(|o| u32::from(k == o)).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let mut counts = vec![1; n];
+        let mut steps = vec![counts.clone()];
+
+        for k in 0..n {
+            for j in (k + 1)..=(k + cards[k].count_winners()).min(n - 1) {
+                counts[j] += counts[k];
+
+                let (processed, rest) = origins.split_at_mut(j);
+
+                for (target, source) in rest[0].iter_mut().zip(&processed[k]).take(k + 1) {
+                    *target += source;
+                }
+            }
+
+            steps.push(counts.clone());
+        }
+
+        Cascade { steps, origins }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.steps.last().map_or(0, |counts| counts.iter().sum())
     }
 
-    counts.iter().sum()
+    /// The copies won, directly or indirectly, by each original card.
+    pub fn contributions(&self) -> Vec<u32> {
+        (0..self.origins.len())
+            .map(|o| self.origins.iter().map(|origins| origins[o]).sum::<u32>() - 1)
+            .collect()
+    }
+
+    /// The (0-based) original card that contributed the most copies, and how many.
+    pub fn top_contributor(&self) -> Option<(usize, u32)> {
+        self.contributions().into_iter().enumerate().max_by_key(|&(o, copies)| (copies, Reverse(o)))
+    }
+
+    /// The counts per step as CSV, one row per step and one column per card.
+    pub fn to_csv(&self) -> String {
+        let n = self.origins.len();
+        let header = once("step".to_string()).chain((1..=n).map(|card| format!("card {card}"))).join(",");
+        let rows = self.steps.iter().enumerate().map(|(step, counts)| once(step).chain(counts.iter().map(|&c| c as usize)).join(","));
+
+        once(header).chain(rows).map(|row| row + "\n").collect()
+    }
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let cascade = Cascade::simulate(&parse(input)?);
+    let mut explanation = cascade.to_csv();
+
+    if let Some((card, copies)) = cascade.top_contributor() {
+        explanation.push_str(&format!("Card {} contributed the most copies: {copies}\n", card + 1));
+    }
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -74,12 +218,12 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(13, part1(&parse(include_str!("../test_input/day04.part1.13.txt")).unwrap()));
+        assert_eq!(13, part1(&parse(include_str!("../test_input/day04.part1.13.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(21105, part1(&parse(include_str!("../input/2023/day4.txt")).unwrap()));
+        assert_eq!(21105, part1(&parse(include_str!("../input/2023/day4.txt")).unwrap()).unwrap());
     }
 
     #[test]
@@ -87,6 +231,37 @@ mod tests {
         assert_eq!(30, part2(&parse(include_str!("../test_input/day04.part2.30.txt")).unwrap()));
     }
 
+    #[test]
+    fn cascade_matches_part2() {
+        let cards = parse(include_str!("../test_input/day04.part2.30.txt")).unwrap();
+        let cascade = Cascade::simulate(&cards);
+
+        assert_eq!(part2(&cards), cascade.total() as u64);
+        assert_eq!(vec![1, 2, 4, 8, 14, 1], cascade.steps[6]);
+        assert_eq!(vec![14, 6, 3, 1, 0, 0], cascade.contributions());
+        assert_eq!(Some((0, 14)), cascade.top_contributor());
+        assert!(cascade.to_csv().starts_with("step,card 1,card 2,card 3,card 4,card 5,card 6\n0,1,1,1,1,1,1\n1,1,2,2,2,2,1\n"));
+    }
+
+    #[test]
+    fn scoring_rules() {
+        let cards = parse(include_str!("../test_input/day04.part1.13.txt")).unwrap();
+
+        assert_eq!(vec![0, 1, 1, 2, 3, 5, 8], (0..=6).map(|n| Points::Fibonacci.score(n).unwrap()).collect::<Vec<_>>());
+        assert_eq!(1 << 63, Points::Exponential.score(64).unwrap());
+        assert!(Points::Exponential.score(65).is_err());
+        assert_eq!(12_200_160_415_121_876_738, Points::Fibonacci.score(93).unwrap());
+        assert!(Points::Fibonacci.score(94).is_err());
+        assert_eq!(4 + 2 + 2 + 1, ScoringRule { points: Points::Linear, ..Default::default() }.total_points(&cards).unwrap());
+        assert_eq!(3 + 1 + 1 + 1, ScoringRule { points: Points::Fibonacci, ..Default::default() }.total_points(&cards).unwrap());
+
+        let doubled = ScoringRule { copies: Copies { reach: 1, multiplier: 2 }, ..Default::default() };
+        assert_eq!(77 + 25 + 9 + 3 + 1 + 1, doubled.total_cards(&cards));
+        let far = ScoringRule { copies: Copies { reach: 2, multiplier: 1 }, ..Default::default() };
+        assert_eq!(24 + 12 + 6 + 3 + 1 + 1, far.total_cards(&cards));
+        assert!("quadratic".parse::<Points>().is_err());
+    }
+
     #[test]
     fn part2_input() {
         assert_eq!(5329815, part2(&parse(include_str!("../input/2023/day4.txt")).unwrap()));
@@ -1,127 +1,348 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use itertools::Itertools;
-use scan_fmt::scan_fmt;
+use crate::parser::{header, numbers, Span};
 
-type Map = HashMap<String, (String, Vec<(i64, i64, i64)>)>;
+/// A function on the integers that adds an offset on each of a set of disjoint half-open ranges and is the identity
+/// everywhere else.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PiecewiseMap {
+    /// Sorted, disjoint `(start, end, offset)` pieces with non-zero offsets, no two adjacent ones sharing an offset.
+    pieces: Vec<(i64, i64, i64)>,
+}
 
-#[aoc_generator(day5)]
-fn parse(input: &str) -> (Vec<i64>, Map) {
-    let seeds = input
-        .lines()
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(str::parse)
-        .map(Result::unwrap)
-        .collect::<Vec<i64>>();
-
-    let mut map = HashMap::new();
-
-    for chunk in input.split("\n\n").skip(1) {
-        let (source, destination) = scan_fmt!(chunk.lines().next().unwrap(), "{}-to-{} map:", String, String).unwrap();
-
-        let mappings: Vec<(i64, i64, i64)> = chunk
-            .lines()
-            .skip(1)
-            .map(|line| scan_fmt!(line, "{d} {d} {d}", i64, i64, i64).unwrap())
-            .map(|(destination_start, source_start, len)| (
-                source_start,
-                source_start + len,
-                destination_start - source_start
-            ))
-            .collect();
+impl PiecewiseMap {
+    pub fn new(pieces: impl IntoIterator<Item = (i64, i64, i64)>) -> Result<Self> {
+        let pieces = pieces.into_iter().filter(|&(start, end, _)| start < end).sorted().collect_vec();
 
-        map.insert(source.to_string(), (destination.to_string(), mappings));
+        if let Some((a, b)) = pieces.iter().tuple_windows().find(|(a, b)| a.1 > b.0) {
+            bail!("Overlapping ranges: {}..{} and {}..{}", a.0, a.1, b.0, b.1);
+        }
+
+        Ok(PiecewiseMap::normalized(pieces))
     }
 
-    (seeds, map)
-}
+    /// From almanac lines of `destination start, source start, length`.
+    pub fn from_ranges(ranges: &[(i64, i64, i64)]) -> Result<Self> {
+        PiecewiseMap::new(ranges.iter().map(|&(destination, source, len)| (source, source + len, destination - source)))
+    }
+
+    fn normalized(pieces: Vec<(i64, i64, i64)>) -> Self {
+        let mut normalized: Vec<(i64, i64, i64)> = vec![];
+
+        for (start, end, offset) in pieces.into_iter().filter(|&(start, end, offset)| start < end && offset != 0) {
+            match normalized.last_mut() {
+                Some(last) if last.1 == start && last.2 == offset => last.1 = end,
+                _ => normalized.push((start, end, offset)),
+            }
+        }
+
+        PiecewiseMap { pieces: normalized }
+    }
+
+    pub fn pieces(&self) -> &[(i64, i64, i64)] {
+        &self.pieces
+    }
 
-fn normalize_mappings(map: &Map, mappings: &[(i64, i64, i64)]) -> Vec<(i64, i64, i64)> {
-    let mut category = &"seed".to_string();
-    let mut mappings = mappings.to_vec();
+    /// The pieces together with the identity gaps between them, covering all of `i64`.
+    fn segments(&self) -> Vec<(i64, i64, i64)> {
+        let mut segments = vec![];
+        let mut position = i64::MIN;
 
-    while category != "location" {
-        let (next_category, next_category_mappings) = map.get(category).unwrap();
+        for &(start, end, offset) in &self.pieces {
+            if position < start {
+                segments.push((position, start, 0));
+            }
 
-        mappings = mappings
+            segments.push((start, end, offset));
+            position = end;
+        }
+
+        if position < i64::MAX {
+            segments.push((position, i64::MAX, 0));
+        }
+
+        segments
+    }
+
+    pub fn apply(&self, x: i64) -> i64 {
+        match self.pieces.iter().find(|&&(start, end, _)| start <= x && x < end) {
+            Some((_, _, offset)) => x + offset,
+            None => x,
+        }
+    }
+
+    /// The image of a set of half-open intervals, as sorted and merged intervals.
+    pub fn apply_intervals(&self, intervals: &[(i64, i64)]) -> Vec<(i64, i64)> {
+        let image = intervals
             .iter()
-            .flat_map(|&mapping| {
-                next_category_mappings
-                    .iter()
-                    .fold(vec![mapping], |acc, &(ncm_start, ncm_end, _)| {
-                        acc.iter()
-                            .flat_map(|&(start, end, diff)| {
-                                if (start + diff >= ncm_start) && (end + diff <= ncm_end) {
-                                    return vec![(start, end, diff)];
-                                } else if (start + diff >= ncm_start) && (start + diff < ncm_end) {
-                                    return vec![
-                                        (start, ncm_end - diff, diff),
-                                        (ncm_end - diff, end, diff)
-                                    ];
-                                } else if (end + diff > ncm_start) && (end + diff < ncm_end) {
-                                    return vec![
-                                        (start, ncm_start - diff, diff),
-                                        (ncm_start - diff, end, diff)
-                                    ];
-                                }
-
-                                vec![(start, end, diff)]
-                            })
-                            .collect()
+            .flat_map(|&(start, end)| {
+                self.segments()
+                    .into_iter()
+                    .filter_map(move |(s, e, offset)| {
+                        let (s, e) = (s.max(start), e.min(end));
+                        (s < e).then_some((s + offset, e + offset))
                     })
-                    .iter()
-                    .map(|&(start, end, diff)| {
-                        next_category_mappings
-                            .iter()
-                            .find(|&&(ncm_start, ncm_end, _)| start + diff >= ncm_start && end + diff <= ncm_end)
-                            .map(|&(_, _, ncm_diff)| (start, end, diff + ncm_diff))
-                            .unwrap_or((start, end, diff))
-                    })
-                    .collect_vec()
             })
+            .sorted();
+
+        let mut merged: Vec<(i64, i64)> = vec![];
+
+        for (start, end) in image {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        merged
+    }
+
+    /// `self` followed by `next`.
+    pub fn compose(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let next_segments = next.segments();
+        let pieces = self
+            .segments()
+            .into_iter()
+            .flat_map(|(start, end, offset)| {
+                next_segments.iter().filter_map(move |&(s, e, next_offset)| {
+                    // Only the unbounded segments of `next` can saturate, and pieces of `self` are bounded.
+                    let (s, e) = (s.saturating_sub(offset).max(start), e.saturating_sub(offset).min(end));
+                    (s < e).then_some((s, e, offset + next_offset))
+                })
+            })
+            .sorted()
+            .collect();
+
+        PiecewiseMap::normalized(pieces)
+    }
+
+    /// Fails if two pieces (or a piece and the identity) map onto the same values.
+    pub fn inverse(&self) -> Result<PiecewiseMap> {
+        let images = self
+            .segments()
+            .into_iter()
+            .map(|(start, end, offset)| (start + offset, end + offset, -offset))
+            .sorted()
             .collect_vec();
 
-        category = next_category;
+        if let Some((a, b)) = images.iter().tuple_windows().find(|(a, b)| a.1 > b.0) {
+            bail!("Not invertible: {}..{} and {}..{} overlap", a.0, a.1, b.0, b.1);
+        }
+
+        Ok(PiecewiseMap::normalized(images))
     }
+}
 
-    mappings
+/// One `source-to-destination map` section, as `(destination start, source start, length)` lines.
+#[derive(Clone, Debug)]
+pub struct AlmanacMap {
+    pub source: String,
+    pub destination: String,
+    pub ranges: Vec<(i64, i64, i64)>,
 }
 
-fn solve(map: &Map, mappings: &[(i64, i64, i64)]) -> i64 {
-    normalize_mappings(map, mappings)
-        .iter()
-        .min_by_key(|(start, _, diff)| start + diff)
-        .map(|(start, _, diff)| start + diff)
-        .unwrap()
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Problem {
+    MissingCategory(String),
+    Cycle(Vec<String>),
+    OverlappingRanges { source: String, destination: String, reason: String },
+    Unreachable { from: String, to: String },
 }
 
-#[aoc(day5, part1)]
-fn part1(input: &(Vec<i64>, Map)) -> i64 {
-    let (seeds, map) = input;
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingCategory(category) => write!(f, "No map from or to {category}"),
+            Problem::Cycle(categories) => write!(f, "Cycle: {}", categories.join(" -> ")),
+            Problem::OverlappingRanges { source, destination, reason } => write!(f, "{source}-to-{destination} map: {reason}"),
+            Problem::Unreachable { from, to } => write!(f, "No route from {from} to {to}"),
+        }
+    }
+}
+
+/// The seeds and a directed graph of categories, with one edge per map. Routes may follow edges backwards.
+#[derive(Clone, Debug)]
+pub struct Almanac {
+    pub seeds: Vec<i64>,
+    pub maps: Vec<AlmanacMap>,
+}
+
+impl Almanac {
+    pub fn categories(&self) -> Vec<&str> {
+        self.maps
+            .iter()
+            .flat_map(|map| [map.source.as_str(), map.destination.as_str()])
+            .unique()
+            .collect()
+    }
+
+    fn cycle(&self) -> Option<Vec<String>> {
+        fn visit<'a>(almanac: &'a Almanac, category: &'a str, path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>) -> Option<Vec<String>> {
+            if let Some(position) = path.iter().position(|&c| c == category) {
+                return Some(path[position..].iter().chain([&category]).map(|c| c.to_string()).collect());
+            }
+
+            if !done.insert(category) {
+                return None;
+            }
+
+            path.push(category);
+            let cycle = almanac.maps
+                .iter()
+                .filter(|map| map.source == category)
+                .find_map(|map| visit(almanac, &map.destination, path, done));
+            path.pop();
+
+            cycle
+        }
+
+        let mut done = HashSet::new();
+        self.categories().into_iter().find_map(|category| visit(self, category, &mut vec![], &mut done))
+    }
+
+    /// The maps to follow from one category to another, each flagged with whether it is followed forwards.
+    fn path(&self, from: &str, to: &str) -> Option<Vec<(&AlmanacMap, bool)>> {
+        let mut previous: HashMap<&str, (&AlmanacMap, bool)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut seen = HashSet::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![];
+                let mut category = to;
+
+                while category != from {
+                    let (map, forwards) = previous[category];
+                    path.push((map, forwards));
+                    category = if forwards { &map.source } else { &map.destination };
+                }
 
-    let initial_mappings = seeds
-        .iter()
-        .map(|&seed| (seed, seed + 1, 0))
-        .collect_vec();
+                path.reverse();
+                return Some(path);
+            }
 
-    solve(map, &initial_mappings)
+            for map in &self.maps {
+                let next = match (map.source == category, map.destination == category) {
+                    (true, _) => (map.destination.as_str(), true),
+                    (_, true) => (map.source.as_str(), false),
+                    _ => continue,
+                };
+
+                if seen.insert(next.0) {
+                    previous.insert(next.0, (map, next.1));
+                    queue.push_back(next.0);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Everything that keeps the almanac from mapping `from` to `to`, and anything else suspicious about it.
+    pub fn validate(&self, from: &str, to: &str) -> Vec<Problem> {
+        let categories = self.categories();
+        let mut problems = vec![];
+
+        for category in [from, to].into_iter().unique() {
+            if !categories.contains(&category) {
+                problems.push(Problem::MissingCategory(category.to_string()));
+            }
+        }
+
+        if let Some(cycle) = self.cycle() {
+            problems.push(Problem::Cycle(cycle));
+        }
+
+        for map in &self.maps {
+            if let Err(e) = PiecewiseMap::from_ranges(&map.ranges) {
+                problems.push(Problem::OverlappingRanges {
+                    source: map.source.clone(),
+                    destination: map.destination.clone(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+
+        if problems.iter().all(|problem| !matches!(problem, Problem::MissingCategory(_))) && self.path(from, to).is_none() {
+            problems.push(Problem::Unreachable { from: from.to_string(), to: to.to_string() });
+        }
+
+        problems
+    }
+
+    /// The map from one category to another, following maps backwards where needed. Problems elsewhere in the graph,
+    /// such as cycles, do not get in the way.
+    pub fn route(&self, from: &str, to: &str) -> Result<PiecewiseMap> {
+        let Some(path) = self.path(from, to) else {
+            bail!("Invalid almanac: {}", self.validate(from, to).iter().join("; "));
+        };
+
+        path.into_iter()
+            .try_fold(PiecewiseMap::default(), |route, (map, forwards)| {
+                let map = PiecewiseMap::from_ranges(&map.ranges)
+                    .with_context(|| format!("Invalid {}-to-{} map", map.source, map.destination))?;
+
+                match forwards {
+                    true => Ok(route.compose(&map)),
+                    false => Ok(route.compose(&map.inverse()?)),
+                }
+            })
+    }
+
+    /// The lowest `to` value of any of the `from` values in `intervals`.
+    pub fn lowest(&self, from: &str, to: &str, intervals: &[(i64, i64)]) -> Result<i64> {
+        match self.route(from, to)?.apply_intervals(intervals).first() {
+            Some(&(lowest, _)) => Ok(lowest),
+            None => bail!("No {from} values"),
+        }
+    }
+
+    /// The seeds line as single values (part 1) or as `start length` pairs (part 2).
+    pub fn seed_intervals(&self, as_ranges: bool) -> Vec<(i64, i64)> {
+        match as_ranges {
+            true => self.seeds.iter().tuples().map(|(&start, &len)| (start, start + len)).collect(),
+            false => self.seeds.iter().map(|&seed| (seed, seed + 1)).collect(),
+        }
+    }
 }
 
-#[aoc(day5, part2)]
-fn part2(input: &(Vec<i64>, Map)) -> i64 {
-    let (seeds, map) = input;
+#[aoc_generator(day5)]
+pub fn parse(input: &str) -> Result<Almanac> {
+    let mut sections = Span::new(input).sections();
+    let seeds_section = sections.next().ok_or_else(|| Span::new(input).error("Input missing seeds"))?;
+    let seeds = numbers(header(seeds_section, "seeds")?)?;
 
-    let initial_mappings = seeds
-        .iter()
-        .tuples()
-        .map(|(start, len)| (*start, start + len, 0))
-        .collect_vec();
+    let mut maps = vec![];
+
+    for chunk in sections {
+        let mut lines = chunk.lines();
+        let title = lines.next().ok_or_else(|| chunk.error("Missing map title"))?;
+        let (source, destination) = title.strip_suffix(" map:")?.split_once("-to-")?;
+
+        let ranges = lines
+            .map(|line| match numbers::<i64>(line)?[..] {
+                [destination_start, source_start, len] => Ok((destination_start, source_start, len)),
+                _ => Err(line.error(format!("Expected three numbers: {line}"))),
+            })
+            .collect::<Result<_>>()?;
 
-    solve(map, &initial_mappings)
+        maps.push(AlmanacMap { source: source.to_string(), destination: destination.to_string(), ranges });
+    }
+
+    Ok(Almanac { seeds, maps })
+}
+
+#[aoc(day5, part1)]
+fn part1(almanac: &Almanac) -> Result<i64> {
+    almanac.lowest("seed", "location", &almanac.seed_intervals(false))
+}
+
+#[aoc(day5, part2)]
+fn part2(almanac: &Almanac) -> Result<i64> {
+    almanac.lowest("seed", "location", &almanac.seed_intervals(true))
 }
 
 #[cfg(test)]
@@ -130,21 +351,66 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(35, part1(&parse(include_str!("../test_input/day05.part1.35.txt"))));
+        assert_eq!(35, part1(&parse(include_str!("../test_input/day05.part1.35.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(346433842, part1(&parse(include_str!("../input/2023/day5.txt"))));
+        assert_eq!(346433842, part1(&parse(include_str!("../input/2023/day5.txt")).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn routes_both_ways() {
+        let almanac = parse(include_str!("../test_input/day05.part1.35.txt")).unwrap();
+        let forward = almanac.route("seed", "location").unwrap();
+        let backward = almanac.route("location", "seed").unwrap();
+
+        assert_eq!(vec![82, 43, 86, 35], almanac.seeds.iter().map(|&seed| forward.apply(seed)).collect_vec());
+        assert_eq!(79, backward.apply(82));
+        assert_eq!(PiecewiseMap::default(), forward.compose(&backward));
+        assert_eq!(74, almanac.route("soil", "light").unwrap().apply(81));
+        assert_eq!(vec![(46, 56), (60, 61)], forward.apply_intervals(&[(82, 93)]));
+        assert!(almanac.route("seed", "planet").is_err());
+    }
+
+    #[test]
+    fn piecewise_map_errors() {
+        assert!(PiecewiseMap::from_ranges(&[(50, 98, 2), (0, 97, 2)]).is_err());
+        assert!(PiecewiseMap::from_ranges(&[(50, 98, 2)]).unwrap().inverse().is_err());
+        assert_eq!(Some(98), PiecewiseMap::from_ranges(&[(50, 98, 2), (98, 50, 2)]).unwrap().inverse().ok().map(|map| map.apply(50)));
+    }
+
+    #[test]
+    fn graph_problems() {
+        let almanac = parse("seeds: 1 2\n\na-to-b map:\n10 0 5\n20 3 5\n\nb-to-c map:\n0 0 1\n\nc-to-a map:\n0 0 1\n\nx-to-y map:\n0 0 1").unwrap();
+        let problems = almanac.validate("a", "y");
+
+        assert_eq!(Problem::Cycle(vec!["a", "b", "c", "a"].into_iter().map(String::from).collect()), problems[0]);
+        assert!(matches!(&problems[1], Problem::OverlappingRanges { source, .. } if source == "a"));
+        assert_eq!(Problem::Unreachable { from: "a".to_string(), to: "y".to_string() }, problems[2]);
+        assert_eq!(Problem::MissingCategory("z".to_string()), almanac.validate("x", "z")[0]);
+        assert!(almanac.route("a", "y").is_err());
+        assert!(almanac.route("a", "b").is_err());
+        assert_eq!(0, almanac.lowest("c", "b", &[(0, 5)]).unwrap());
+        assert_eq!(3, almanac.lowest("y", "x", &[(3, 5)]).unwrap());
+    }
+
+    #[test]
+    fn lowest_between_any_categories() {
+        let almanac = parse(include_str!("../test_input/day05.part1.35.txt")).unwrap();
+
+        assert_eq!(46, almanac.lowest("seed", "location", &almanac.seed_intervals(true)).unwrap());
+        assert_eq!(13, almanac.lowest("seed", "soil", &almanac.seed_intervals(false)).unwrap());
+        assert_eq!(82, almanac.lowest("location", "seed", &[(46, 47)]).unwrap());
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(46, part2(&parse(include_str!("../test_input/day05.part2.46.txt"))));
+        assert_eq!(46, part2(&parse(include_str!("../test_input/day05.part2.46.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(60294664, part2(&parse(include_str!("../input/2023/day5.txt"))));
+        assert_eq!(60294664, part2(&parse(include_str!("../input/2023/day5.txt")).unwrap()).unwrap());
     }
 }
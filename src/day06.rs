@@ -1,6 +1,8 @@
 use std::iter::zip;
 use aoc_runner_derive::{aoc, aoc_generator};
 use anyhow::{Context, Result};
+use num_bigint::BigUint;
+use num_integer::{Integer, Roots};
 
 type Input = (Vec<(u64, u64)>, (u64, u64));
 
@@ -46,27 +48,153 @@ fn parse(input: &str) -> Result<Input> {
 
 }
 
-fn count_wins(time: f64, distance: f64) -> usize {
-    let r1 = ((time + (time * time - 4.0 * distance).sqrt()) / 2.0).ceil() as usize - 1;
-    let r2 = ((time - (time * time - 4.0 * distance).sqrt()) / 2.0).floor() as usize + 1;
+/// The number of hold times `h` for which `h * (time - h) > distance`, using an integer square root of the
+/// discriminant as a first guess for the shortest winning hold time and then correcting it.
+pub fn count_wins<T>(time: &T, distance: &T) -> T where T: Integer + Roots + Clone + From<u8> {
+    let (one, two, four) = (T::from(1), T::from(2), T::from(4));
+    let beats = |hold: &T| hold.clone() * (time.clone() - hold.clone()) > *distance;
+    let half = time.clone() / two.clone();
 
-    r1 - r2 + 1
+    if !beats(&half) {
+        return T::zero();
+    }
+
+    let discriminant = time.clone() * time.clone() - four * distance.clone();
+    let mut shortest = (time.clone() - discriminant.sqrt()) / two.clone();
+
+    while !beats(&shortest) {
+        shortest = shortest + one.clone();
+    }
+
+    while !shortest.is_zero() && beats(&(shortest.clone() - one.clone())) {
+        shortest = shortest - one.clone();
+    }
+
+    time.clone() - two * shortest + one
 }
 
-#[aoc(day6, part1)]
-fn part1(input: &Input) -> usize {
-    let (input, _) = input;
+/// [`count_wins`] for numbers of any size.
+pub fn count_wins_big(time: &str, distance: &str) -> Result<BigUint> {
+    let time = time.parse().context(format!("Could not parse number: {time}"))?;
+    let distance = distance.parse().context(format!("Could not parse number: {distance}"))?;
+
+    Ok(count_wins::<BigUint>(&time, &distance))
+}
+
+/// How far a boat gets in a race, given how long its button was held. The distance must increase with the hold time up
+/// to some best hold time and must not increase after it.
+pub trait BoatModel {
+    fn distance(&self, hold: u64, time: u64) -> u64;
+
+    fn best_hold(&self, time: u64) -> u64 {
+        first_true(0, time, |hold| self.distance(hold + 1, time) <= self.distance(hold, time))
+    }
+
+    /// The number of hold times that beat `record`, found by binary search on either side of the best hold time.
+    fn count_wins(&self, time: u64, record: u64) -> u64 {
+        let best = self.best_hold(time);
+
+        if self.distance(best, time) <= record {
+            return 0;
+        }
+
+        let shortest = first_true(0, best, |hold| self.distance(hold, time) > record);
+        let longest = first_true(best, time + 1, |hold| self.distance(hold, time) <= record) - 1;
+
+        longest - shortest + 1
+    }
+}
+
+/// The smallest value in `low..high` for which `predicate` holds, or `high` if there is none, given that `predicate`
+/// is false up to some point and true after it.
+fn first_true(mut low: u64, mut high: u64, predicate: impl Fn(u64) -> bool) -> u64 {
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        match predicate(middle) {
+            true => high = middle,
+            false => low = middle + 1,
+        }
+    }
+
+    low
+}
+
+/// Holding the button adds `acceleration` to the speed per millisecond.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Linear {
+    pub acceleration: u64,
+}
+
+impl BoatModel for Linear {
+    fn distance(&self, hold: u64, time: u64) -> u64 {
+        self.acceleration * hold * time.saturating_sub(hold)
+    }
+
+    fn best_hold(&self, time: u64) -> u64 {
+        time / 2
+    }
 
-    input
+    fn count_wins(&self, time: u64, record: u64) -> u64 {
+        match self.acceleration {
+            0 => 0,
+            acceleration => count_wins(&time, &(record / acceleration)),
+        }
+    }
+}
+
+/// Like [`Linear`], but the speed never exceeds `max_speed`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Capped {
+    pub acceleration: u64,
+    pub max_speed: u64,
+}
+
+impl BoatModel for Capped {
+    fn distance(&self, hold: u64, time: u64) -> u64 {
+        (self.acceleration * hold).min(self.max_speed) * time.saturating_sub(hold)
+    }
+}
+
+/// Like [`Linear`], but once released the boat loses `drag` speed after every millisecond until it stops. Without drag
+/// it never stops.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Drag {
+    pub acceleration: u64,
+    pub drag: u64,
+}
+
+impl BoatModel for Drag {
+    fn distance(&self, hold: u64, time: u64) -> u64 {
+        let speed = self.acceleration * hold;
+        let moving = match self.drag {
+            0 => time.saturating_sub(hold),
+            drag => time.saturating_sub(hold).min(speed.div_ceil(drag)),
+        };
+
+        moving * speed - self.drag * moving * moving.saturating_sub(1) / 2
+    }
+}
+
+pub const STANDARD: Linear = Linear { acceleration: 1 };
+
+pub fn race_product(model: &impl BoatModel, races: &[(u64, u64)]) -> u64 {
+    races
         .iter()
-        .map(|&(time, distance)| count_wins(time as f64, distance as f64))
+        .map(|&(time, record)| model.count_wins(time, record))
         .product()
 }
 
+#[aoc(day6, part1)]
+fn part1(input: &Input) -> u64 {
+    let (races, _) = input;
+    race_product(&STANDARD, races)
+}
+
 #[aoc(day6, part2)]
-fn part2(input: &Input) -> usize {
-    let (_, (time, distance)) = *input;
-    count_wins(time as f64, distance as f64)
+fn part2(input: &Input) -> u64 {
+    let (_, race) = *input;
+    race_product(&STANDARD, &[race])
 }
 
 #[cfg(test)]
@@ -82,6 +210,46 @@ mod tests {
     fn part1_input() {
         assert_eq!(503424, part1(&parse(include_str!("../input/2023/day6.txt")).unwrap()));
     }
+    #[test]
+    fn exact_counts() {
+        for time in 0..40u64 {
+            for distance in 0..(time * time / 4 + 2) {
+                let brute_force = (0..=time).filter(|hold| hold * (time - hold) > distance).count() as u64;
+                assert_eq!(brute_force, count_wins(&time, &distance), "time {time}, distance {distance}");
+            }
+        }
+
+        let time = 2 * 10u128.pow(18);
+        assert_eq!(1, count_wins(&time, &(10u128.pow(36) - 1)));
+        assert_eq!(0, count_wins(&time, &10u128.pow(36)));
+
+        let time = format!("2{}", "0".repeat(40));
+        assert_eq!(BigUint::from(1u8), count_wins_big(&time, &"9".repeat(80)).unwrap());
+        assert_eq!(BigUint::from(0u8), count_wins_big(&time, &format!("1{}", "0".repeat(80))).unwrap());
+        assert_eq!(BigUint::from(3u8), count_wins_big(&time, &format!("{}6", "9".repeat(79))).unwrap());
+    }
+
+    fn assert_matches_brute_force(model: &impl BoatModel) {
+        for time in 0..30 {
+            for record in 0..200 {
+                let brute_force = (0..=time).filter(|&hold| model.distance(hold, time) > record).count() as u64;
+                assert_eq!(brute_force, model.count_wins(time, record), "time {time}, record {record}");
+            }
+        }
+    }
+
+    #[test]
+    fn boat_models() {
+        assert_matches_brute_force(&Linear { acceleration: 3 });
+        assert_matches_brute_force(&Capped { acceleration: 2, max_speed: 7 });
+        assert_matches_brute_force(&Drag { acceleration: 2, drag: 3 });
+        assert_eq!(1, Capped { acceleration: 1, max_speed: 2 }.count_wins(7, 9));
+
+        assert_matches_brute_force(&Linear { acceleration: 0 });
+        assert_matches_brute_force(&Drag { acceleration: 2, drag: 0 });
+        assert_eq!(Linear { acceleration: 2 }.count_wins(7, 9), Drag { acceleration: 2, drag: 0 }.count_wins(7, 9));
+    }
+
     #[test]
     fn part2_example1() {
         assert_eq!(71503, part2(&parse(include_str!("../test_input/day06.part2.71503.txt")).unwrap()));
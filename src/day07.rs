@@ -1,4 +1,5 @@
-use std::fmt::{Display, Formatter};
+use std::cmp::{Ordering, Reverse};
+use std::fmt::{Display, Formatter, Write};
 use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
 use anyhow::{Context, Result, Error, bail};
@@ -6,8 +7,8 @@ use itertools::Itertools;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone, EnumIter)]
-enum Card {
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Copy, Clone, EnumIter)]
+pub enum Card {
     Num2,
     Num3,
     Num4,
@@ -46,8 +47,28 @@ impl TryFrom<char> for Card {
     }
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq)]
-enum HandType {
+impl Display for Card {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_char(match self {
+            Card::Num2 => '2',
+            Card::Num3 => '3',
+            Card::Num4 => '4',
+            Card::Num5 => '5',
+            Card::Num6 => '6',
+            Card::Num7 => '7',
+            Card::Num8 => '8',
+            Card::Num9 => '9',
+            Card::T => 'T',
+            Card::J => 'J',
+            Card::Q => 'Q',
+            Card::K => 'K',
+            Card::A => 'A',
+        })
+    }
+}
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone)]
+pub enum HandType {
     HighCard,
     OnePair,
     TwoPairs,
@@ -57,36 +78,24 @@ enum HandType {
     FiveOfAKind,
 }
 
-impl From<[Card; 5]> for HandType {
-    fn from(cards: [Card; 5]) -> Self {
-        let card_type_counts = cards
-            .into_iter()
-            .sorted()
-            .rev()
-            .group_by(|c| *c)
-            .into_iter()
-            .map(|(_, g)| g.count())
-            .sorted()
-            .rev()
-            .collect_vec();
-
-        match &card_type_counts[..] {
-            [5] => HandType::FiveOfAKind,
-            [4, 1] => HandType::FourOfAKind,
-            [3, 2] => HandType::FullHouse,
-            [3, 1, 1] => HandType::ThreeOfAKind,
-            [2, 2, 1] => HandType::TwoPairs,
-            [2, 1, 1, 1] => HandType::OnePair,
-            [1, 1, 1, 1, 1] => HandType::HighCard,
-            _ => unreachable!()
+impl HandType {
+    /// Classifies by the two largest counts of equal cards, which works for hands of any size.
+    pub fn from_histogram(histogram: &[usize]) -> Self {
+        match histogram {
+            [n, ..] if *n >= 5 => HandType::FiveOfAKind,
+            [n, ..] if *n >= 4 => HandType::FourOfAKind,
+            [n, m, ..] if *n >= 3 && *m >= 2 => HandType::FullHouse,
+            [n, ..] if *n >= 3 => HandType::ThreeOfAKind,
+            [n, m, ..] if *n >= 2 && *m >= 2 => HandType::TwoPairs,
+            [n, ..] if *n >= 2 => HandType::OnePair,
+            _ => HandType::HighCard,
         }
     }
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq)]
-struct Hand {
-    hand_type: HandType,
-    cards: [Card; 5],
+#[derive(Clone, Eq, PartialEq)]
+pub struct Hand {
+    pub cards: Vec<Card>,
     string_representation: String,
 }
 
@@ -94,21 +103,17 @@ impl FromStr for Hand {
     type Err = Error;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        if s.len() != 5 {
+        if s.is_empty() {
             bail!("Invalid hand: {s}");
         }
 
-        let cards: [Card; 5] = s
+        let cards = s
             .chars()
             .map(Card::try_from)
-            .collect::<Result<Vec<Card>>>()?
-            .try_into()
-            .unwrap();
-
-        let hand_type = cards.into();
+            .collect::<Result<Vec<Card>>>()?;
         let string_representation = s.to_string();
 
-        Ok(Hand { hand_type, cards, string_representation })
+        Ok(Hand { cards, string_representation })
     }
 }
 
@@ -118,8 +123,246 @@ impl Display for Hand {
     }
 }
 
+/// The rules for ranking hands: by type first, then card by card.
+#[derive(Clone)]
+pub struct Ranking {
+    /// Cards from weakest to strongest when breaking ties.
+    pub order: Vec<Card>,
+    /// Cards that count as whatever makes the best hand type, each with its own tie-break value (relative to the
+    /// positions in `order`).
+    pub wildcards: Vec<(Card, i32)>,
+    pub hand_size: usize,
+    /// Hand type from the counts of equal cards, largest first.
+    pub classifier: fn(&[usize]) -> HandType,
+}
+
+impl Ranking {
+    pub fn standard() -> Self {
+        Ranking {
+            order: Card::iter().collect(),
+            wildcards: vec![],
+            hand_size: 5,
+            classifier: HandType::from_histogram,
+        }
+    }
+
+    /// Jokers are wild, and weaker than any other card when breaking ties.
+    pub fn jokers() -> Self {
+        Ranking { wildcards: vec![(Card::J, -1)], ..Ranking::standard() }
+    }
+
+    fn wildcard_value(&self, card: Card) -> Option<i32> {
+        self.wildcards.iter().find(|&&(wildcard, _)| wildcard == card).map(|&(_, value)| value)
+    }
+
+    fn value(&self, card: Card) -> Result<i32> {
+        match self.wildcard_value(card) {
+            Some(value) => Ok(value),
+            None => match self.order.iter().position(|&c| c == card) {
+                Some(position) => Ok(position as i32),
+                None => bail!("Card {card} is not ranked"),
+            },
+        }
+    }
+
+    /// The counts of equal cards, largest first, with the wildcards joining the largest group. Also returns the card
+    /// the wildcards stand in for, if there are any.
+    pub fn histogram(&self, hand: &Hand) -> Result<(Vec<usize>, Option<Card>)> {
+        let wildcards = hand.cards.iter().filter(|&&card| self.wildcard_value(card).is_some()).count();
+        let groups = hand.cards
+            .iter()
+            .filter(|&&card| self.wildcard_value(card).is_none())
+            .counts()
+            .into_iter()
+            .map(|(&card, count)| Ok((count, self.value(card)?, card)))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .sorted()
+            .rev()
+            .collect_vec();
+
+        let mut histogram = groups.iter().map(|&(count, _, _)| count).collect_vec();
+        let substitute = match (wildcards, groups.first(), self.order.last()) {
+            (0, _, _) => None,
+            (_, Some(&(_, _, card)), _) => Some(card),
+            (_, None, strongest) => strongest.copied(),
+        };
+
+        match histogram.first_mut() {
+            Some(largest) => *largest += wildcards,
+            None => histogram.push(wildcards),
+        }
+
+        Ok((histogram, substitute))
+    }
+
+    pub fn hand_type(&self, hand: &Hand) -> Result<HandType> {
+        let (histogram, _) = self.histogram(hand)?;
+        Ok((self.classifier)(&histogram))
+    }
+
+    fn key(&self, hand: &Hand) -> Result<(HandType, Vec<i32>)> {
+        if hand.cards.len() != self.hand_size {
+            bail!("Expected {} cards: {hand}", self.hand_size);
+        }
+
+        Ok((self.hand_type(hand)?, hand.cards.iter().map(|&card| self.value(card)).collect::<Result<_>>()?))
+    }
+
+    /// The hands from weakest to strongest.
+    pub fn rank<'a>(&self, hands: impl IntoIterator<Item = &'a Hand>) -> Result<Vec<&'a Hand>> {
+        let keyed = hands
+            .into_iter()
+            .map(|hand| Ok((self.key(hand)?, hand)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(keyed.into_iter().sorted_by(|(a, _), (b, _)| a.cmp(b)).map(|(_, hand)| hand).collect())
+    }
+
+    pub fn winnings(&self, hands: &[(Hand, u32)]) -> Result<u32> {
+        let keyed = hands
+            .iter()
+            .map(|(hand, bid)| Ok((self.key(hand)?, bid)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(
+            keyed
+                .into_iter()
+                .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                .enumerate()
+                .map(|(rank, (_, bid))| (rank + 1) as u32 * bid)
+                .sum()
+        )
+    }
+}
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Copy, Clone)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl TryFrom<char> for Suit {
+    type Error = Error;
+
+    fn try_from(value: char) -> std::result::Result<Self, Self::Error> {
+        match value {
+            'C' => Ok(Suit::Clubs),
+            'D' => Ok(Suit::Diamonds),
+            'H' => Ok(Suit::Hearts),
+            'S' => Ok(Suit::Spades),
+            _ => bail!("Invalid suit: {value}"),
+        }
+    }
+}
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone)]
+pub enum PokerCategory {
+    HighCard,
+    OnePair,
+    TwoPairs,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+/// Five suited cards, such as `AS KD 3H 3C 9S`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PokerHand {
+    pub cards: Vec<(Card, Suit)>,
+}
+
+impl FromStr for PokerHand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let cards = s
+            .split_whitespace()
+            .map(|card| match card.chars().collect_vec()[..] {
+                [rank, suit] => Ok((Card::try_from(rank)?, Suit::try_from(suit)?)),
+                _ => bail!("Invalid card: {card}"),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if cards.len() != 5 || !cards.iter().all_unique() {
+            bail!("Invalid poker hand: {s}");
+        }
+
+        Ok(PokerHand { cards })
+    }
+}
+
+impl Display for PokerHand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suit = |suit: &Suit| match suit {
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+            Suit::Hearts => 'H',
+            Suit::Spades => 'S',
+        };
+
+        f.write_str(&self.cards.iter().map(|(card, s)| format!("{card}{}", suit(s))).join(" "))
+    }
+}
+
+impl PokerHand {
+    /// The category, and the ranks that break ties within it, most significant first.
+    pub fn evaluate(&self) -> (PokerCategory, Vec<Card>) {
+        let groups = self.cards
+            .iter()
+            .map(|&(card, _)| card)
+            .counts()
+            .into_iter()
+            .map(|(card, count)| (count, card))
+            .sorted()
+            .rev()
+            .collect_vec();
+        let counts = groups.iter().map(|&(count, _)| count).collect_vec();
+        let ranks = groups.iter().map(|&(_, card)| card).collect_vec();
+
+        let flush = self.cards.iter().map(|&(_, suit)| suit).all_equal();
+        let straight_high = match ranks[..] {
+            [Card::A, Card::Num5, Card::Num4, Card::Num3, Card::Num2] => Some(Card::Num5),
+            [high, .., low] if counts.len() == 5 && high as usize - low as usize == 4 => Some(high),
+            _ => None,
+        };
+
+        match (straight_high, flush, &counts[..]) {
+            (Some(high), true, _) => (PokerCategory::StraightFlush, vec![high]),
+            (_, _, [4, 1]) => (PokerCategory::FourOfAKind, ranks),
+            (_, _, [3, 2]) => (PokerCategory::FullHouse, ranks),
+            (_, true, _) => (PokerCategory::Flush, ranks),
+            (Some(high), _, _) => (PokerCategory::Straight, vec![high]),
+            (_, _, [3, 1, 1]) => (PokerCategory::ThreeOfAKind, ranks),
+            (_, _, [2, 2, 1]) => (PokerCategory::TwoPairs, ranks),
+            (_, _, [2, 1, 1, 1]) => (PokerCategory::OnePair, ranks),
+            _ => (PokerCategory::HighCard, ranks),
+        }
+    }
+
+    /// How `self` fares against `other`, and the category of the winning hand (or of both, on a tie).
+    pub fn showdown(&self, other: &PokerHand) -> (Ordering, PokerCategory) {
+        let (a, b) = (self.evaluate(), other.evaluate());
+
+        match a.cmp(&b) {
+            Ordering::Less => (Ordering::Less, b.0),
+            ordering => (ordering, a.0),
+        }
+    }
+}
+
+/// The hands from strongest to weakest.
+pub fn rank_poker_hands(hands: &[PokerHand]) -> Vec<&PokerHand> {
+    hands.iter().sorted_by_cached_key(|hand| Reverse(hand.evaluate())).collect()
+}
+
 #[aoc_generator(day7)]
-fn parse(input: &str) -> Result<Vec<(Hand, u32)>> {
+pub fn parse(input: &str) -> Result<Vec<(Hand, u32)>> {
     input
         .lines()
         .map(|line| {
@@ -133,60 +376,32 @@ fn parse(input: &str) -> Result<Vec<(Hand, u32)>> {
 }
 
 #[aoc(day7, part1)]
-fn part1(input: &[(Hand, u32)]) -> u32 {
-    input
-        .iter()
-        .sorted_by_key(|(hand, _)| hand)
-        .enumerate()
-        .map(|(rank, (_, bid))| (rank + 1) as u32 * bid)
-        .sum()
+fn part1(input: &[(Hand, u32)]) -> Result<u32> {
+    Ranking::standard().winnings(input)
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &[(Hand, u32)]) -> u32 {
-    input
-        .iter()
-        .sorted_by_key(|(hand, _)| {
-            let hand_type = Card::iter()
-                .map(|substitute| {
-                    let mut cards = hand.cards;
-
-                    for card in &mut cards {
-                        if *card == Card::J {
-                            *card = substitute;
-                        }
-                    }
-
-                    HandType::from(cards)
-                })
-                .max();
-
-            let cards_proxy: [u8; 5] = hand.cards
-                .into_iter()
-                .map(|card| match card {
-                    Card::J => 0,
-                    Card::Num2 => 1,
-                    Card::Num3 => 2,
-                    Card::Num4 => 3,
-                    Card::Num5 => 4,
-                    Card::Num6 => 5,
-                    Card::Num7 => 6,
-                    Card::Num8 => 7,
-                    Card::Num9 => 8,
-                    Card::T => 9,
-                    Card::Q => 10,
-                    Card::K => 11,
-                    Card::A => 12,
-                })
-                .collect_vec()
-                .try_into()
-                .unwrap();
-
-            (hand_type, cards_proxy)
-        })
-        .enumerate()
-        .map(|(rank, (_, bid))| (rank + 1) as u32 * bid)
-        .sum()
+fn part2(input: &[(Hand, u32)]) -> Result<u32> {
+    Ranking::jokers().winnings(input)
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let mut explanation = String::new();
+    let (standard, jokers) = (Ranking::standard(), Ranking::jokers());
+
+    for (hand, bid) in parse(input)? {
+        let (histogram, substitute) = jokers.histogram(&hand)?;
+        let substitute = substitute.map_or("-".to_string(), |card| format!("J→{card}"));
+
+        writeln!(
+            explanation,
+            "{hand} {bid}: {:?}; with jokers {substitute} {:?}",
+            standard.hand_type(&hand)?,
+            (jokers.classifier)(&histogram),
+        )?;
+    }
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -195,21 +410,81 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(6440, part1(&parse(include_str!("../test_input/day07.part1.6440.txt")).unwrap()));
+        assert_eq!(6440, part1(&parse(include_str!("../test_input/day07.part1.6440.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(248179786, part1(&parse(include_str!("../input/2023/day7.txt")).unwrap()));
+        assert_eq!(248179786, part1(&parse(include_str!("../input/2023/day7.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(5905, part2(&parse(include_str!("../test_input/day07.part2.5905.txt")).unwrap()));
+        assert_eq!(5905, part2(&parse(include_str!("../test_input/day07.part2.5905.txt")).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn configurable_ranking() {
+        let hands = ["JJJJJJ", "2345JJ", "QQQ222", "22JJ33"].map(|hand| hand.parse::<Hand>().unwrap());
+        let mut six_cards = Ranking { wildcards: vec![(Card::J, 20), (Card::Num2, -1)], hand_size: 6, ..Ranking::standard() };
+
+        let histograms = hands.iter().map(|hand| six_cards.histogram(hand).unwrap()).collect_vec();
+        assert_eq!((vec![6], Some(Card::A)), histograms[0]);
+        assert_eq!((vec![4, 1, 1], Some(Card::Num5)), histograms[1]);
+        assert_eq!(HandType::FiveOfAKind, six_cards.hand_type(&hands[2]).unwrap());
+        assert_eq!(
+            vec!["2345JJ", "22JJ33", "QQQ222", "JJJJJJ"],
+            six_cards.rank(&hands).unwrap().iter().map(|hand| hand.to_string()).collect_vec(),
+        );
+
+        six_cards.classifier = |_| HandType::HighCard;
+        assert_eq!(
+            vec!["22JJ33", "2345JJ", "QQQ222", "JJJJJJ"],
+            six_cards.rank(&hands).unwrap().iter().map(|hand| hand.to_string()).collect_vec(),
+        );
+        assert!(Ranking::standard().rank(&hands).is_err());
+
+        let hands = ["333222", "22233K", "2222KK", "223344"].map(|hand| hand.parse::<Hand>().unwrap());
+        let six_cards = Ranking { hand_size: 6, ..Ranking::standard() };
+        assert_eq!(HandType::FullHouse, six_cards.hand_type(&hands[0]).unwrap());
+        assert_eq!(HandType::TwoPairs, six_cards.hand_type(&hands[3]).unwrap());
+        assert_eq!(
+            vec!["223344", "22233K", "333222", "2222KK"],
+            six_cards.rank(&hands).unwrap().iter().map(|hand| hand.to_string()).collect_vec(),
+        );
+    }
+
+    #[test]
+    fn poker() {
+        let hand = |s: &str| s.parse::<PokerHand>().unwrap();
+
+        assert_eq!((PokerCategory::OnePair, vec![Card::Num3, Card::A, Card::K, Card::Num9]), hand("AS KD 3H 3C 9S").evaluate());
+        assert_eq!(PokerCategory::Straight, hand("AS 2D 3H 4C 5S").evaluate().0);
+        assert_eq!(PokerCategory::StraightFlush, hand("TH JH QH KH AH").evaluate().0);
+        assert_eq!(PokerCategory::Flush, hand("2H 7H QH KH AH").evaluate().0);
+        assert_eq!(PokerCategory::FullHouse, hand("2H 2S QH QD QC").evaluate().0);
+
+        assert_eq!((Ordering::Greater, PokerCategory::OnePair), hand("AS KD 3H 3C 9S").showdown(&hand("AD QD 3S 3D 9C")));
+        assert_eq!((Ordering::Less, PokerCategory::Straight), hand("AS 2D 3H 4C 5S").showdown(&hand("2S 3D 4H 5C 6S")));
+        assert_eq!((Ordering::Equal, PokerCategory::TwoPairs), hand("AS AD 3H 3C 9S").showdown(&hand("AH AC 3S 3D 9C")));
+
+        let hands = ["2H 2S QH QD QC", "AS KD 3H 3C 9S", "TH JH QH KH AH", "2D 7D QD KD AD"].map(hand);
+        assert_eq!(vec![&hands[2], &hands[0], &hands[3], &hands[1]], rank_poker_hands(&hands));
+
+        assert!("AS KD 3H 3C".parse::<PokerHand>().is_err());
+        assert!("AS AS 3H 3C 9S".parse::<PokerHand>().is_err());
+        assert!("AS KX 3H 3C 9S".parse::<PokerHand>().is_err());
+    }
+
+    #[test]
+    fn explain_example1() {
+        let explanation = explain(include_str!("../test_input/day07.part2.5905.txt")).unwrap();
+        assert!(explanation.contains("KTJJT 220: TwoPairs; with jokers J→T FourOfAKind"));
+        assert!(explanation.contains("32T3K 765: OnePair; with jokers - OnePair"));
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(247885995, part2(&parse(include_str!("../input/2023/day7.txt")).unwrap()));
+        assert_eq!(247885995, part2(&parse(include_str!("../input/2023/day7.txt")).unwrap()).unwrap());
     }
 }
@@ -1,10 +1,11 @@
 use std::collections::HashMap;
+use std::fmt::Write;
 use aoc_runner_derive::{aoc, aoc_generator};
-use scan_fmt::scan_fmt;
-use anyhow::{bail, Error};
+use anyhow::{bail, Error, Result};
 use itertools::Itertools;
+use crate::parser::{labelled_tuple, Span};
 
-enum Instruction {
+pub enum Instruction {
     Left,
     Right,
 }
@@ -12,7 +13,7 @@ enum Instruction {
 impl TryFrom<char> for Instruction {
     type Error = Error;
 
-    fn try_from(value: char) -> Result<Self, Self::Error> {
+    fn try_from(value: char) -> Result<Self> {
         match value {
             'L' => Ok(Instruction::Left),
             'R' => Ok(Instruction::Right),
@@ -21,43 +22,140 @@ impl TryFrom<char> for Instruction {
     }
 }
 
-type Input = (Vec<Instruction>, HashMap<String, (String, String)>);
+pub type Input = (Vec<Instruction>, HashMap<String, (String, String)>);
 
 #[aoc_generator(day8)]
-fn parse(input: &str) -> Input {
-    let (instructions, map) = input.split_once("\n\n").unwrap();
-    let instructions = instructions.chars().map(Instruction::try_from).map(Result::unwrap).collect();
+pub fn parse(input: &str) -> Result<Input> {
+    let (instructions, map) = Span::new(input).split_once("\n\n")?;
+    if instructions.as_str().is_empty() {
+        return Err(instructions.error("Expected instructions"));
+    }
+
+    let instructions = instructions
+        .chars()
+        .map(|(c, span)| Instruction::try_from(c).map_err(|e| span.error(e)))
+        .collect::<Result<_>>()?;
 
     let map = map.lines()
-        .map(|line| {
-            let (from, l, r) = scan_fmt!(line, "{} = ({}, {})", String, String, String).unwrap();
-            (from, (l, r))
+        .map(|line| match labelled_tuple(line)? {
+            (from, tuple) if tuple.len() == 2 => Ok((from.to_string(), (tuple[0].to_string(), tuple[1].to_string()))),
+            _ => Err(line.error(format!("Expected a pair: {line}"))),
         })
-        .collect();
+        .collect::<Result<_>>()?;
+
+    Ok((instructions, map))
+}
 
-    (instructions, map)
+/// The network with node names interned to indices, plus jump tables for whole passes over the instructions.
+pub struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// 0 for left, 1 for right.
+    instructions: Vec<usize>,
+    next: Vec<[usize; 2]>,
+    /// `passes[k][node]`: where `node` ends up after 2^k passes over the instructions.
+    passes: Vec<Vec<usize>>,
 }
 
-fn distance(start: &str, end: &str, input: &Input) -> usize {
-    let (instructions, map) = input;
+impl Network {
+    pub fn compile(input: &Input) -> Result<Self> {
+        let (instructions, map) = input;
+        let names = map.keys().sorted().cloned().collect_vec();
+        let ids: HashMap<String, usize> = names.iter().enumerate().map(|(id, name)| (name.clone(), id)).collect();
+
+        let id = |name: &String| match ids.get(name) {
+            Some(&id) => Ok(id),
+            None => bail!("Unknown node: {name}"),
+        };
+        let next = names
+            .iter()
+            .map(|name| {
+                let (left, right) = &map[name];
+                Ok([id(left)?, id(right)?])
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let instructions = instructions
+            .iter()
+            .map(|instruction| match instruction {
+                Instruction::Left => 0,
+                Instruction::Right => 1,
+            })
+            .collect_vec();
 
-    let mut location = start;
-    let mut distance = 0;
+        let one_pass = (0..names.len())
+            .map(|node| instructions.iter().fold(node, |node, &direction| next[node][direction]))
+            .collect_vec();
+        let mut passes = vec![one_pass];
 
-    for instruction in instructions.iter().cycle() {
-        if location.ends_with(end) {
-            break;
+        for _ in 1..u64::BITS {
+            let last = passes.last().unwrap();
+            passes.push(last.iter().map(|&node| last[node]).collect());
         }
 
-        location = match instruction {
-            Instruction::Left => &map.get(location).unwrap().0,
-            Instruction::Right => &map.get(location).unwrap().1,
-        };
+        Ok(Network { names, ids, instructions, next, passes })
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    /// Where a walk from `start` is after `steps` steps, jumping whole passes by binary lifting.
+    pub fn position_after(&self, start: usize, steps: u64) -> usize {
+        let (full_passes, rest) = (steps / self.instructions.len() as u64, steps % self.instructions.len() as u64);
+        let node = (0..u64::BITS as usize)
+            .filter(|&k| full_passes & (1 << k) != 0)
+            .fold(start, |node, k| self.passes[k][node]);
 
-        distance += 1;
+        self.instructions[..(rest as usize)].iter().fold(node, |node, &direction| self.next[node][direction])
     }
 
-    distance
+    /// The number of steps until a walk from `start` first reaches a target node, or `None` if it never does.
+    pub fn steps_until(&self, start: usize, is_target: impl Fn(usize) -> bool) -> Option<u64> {
+        // For each node, the first step within a pass starting there that is on a target.
+        let first_in_pass = (0..self.names.len())
+            .map(|node| {
+                let mut node = node;
+
+                (0..self.instructions.len()).find(|&i| {
+                    let found = is_target(node);
+                    node = self.next[node][self.instructions[i]];
+                    found
+                })
+            })
+            .collect_vec();
+
+        let mut seen = vec![false; self.names.len()];
+        let mut node = start;
+        let mut steps = 0;
+
+        while !seen[node] {
+            if let Some(i) = first_in_pass[node] {
+                return Some(steps + i as u64);
+            }
+
+            seen[node] = true;
+            node = self.passes[0][node];
+            steps += self.instructions.len() as u64;
+        }
+
+        None
+    }
+}
+
+fn distance(start: &str, end: &str, input: &Input) -> Result<u64> {
+    let network = Network::compile(input)?;
+    let (Some(start), Some(end)) = (network.id(start), network.id(end)) else {
+        bail!("Unknown node: {start} or {end}");
+    };
+
+    match network.steps_until(start, |node| node == end) {
+        Some(steps) => Ok(steps),
+        None => bail!("{} never reaches {}", network.name(start), network.name(end)),
+    }
 }
 
 fn gcd(a: usize, b: usize) -> usize {
@@ -76,25 +174,188 @@ fn lcm(a: usize, b: usize) -> usize {
     (a * b) / gcd(a, b)
 }
 
+/// Returns `(g, x)` with `g = gcd(a, m)` and `a * x ≡ g (mod m)`.
+fn extended_gcd(a: i128, m: i128) -> (i128, i128) {
+    let (mut old_r, mut r) = (a, m);
+    let (mut old_s, mut s) = (1, 0);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+
+    (old_r, old_s)
+}
+
+/// The solutions of `x ≡ r1 (mod m1)` and `x ≡ r2 (mod m2)`, as a single congruence, if there are any.
+fn combine((r1, m1): (i128, i128), (r2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, inverse) = extended_gcd(m1, m2);
+
+    if (r2 - r1) % g != 0 {
+        return None;
+    }
+
+    let modulus = m1 / g * m2;
+    let k = ((r2 - r1) / g % (m2 / g)) * inverse % (m2 / g);
+
+    Some(((r1 + m1 * k).rem_euclid(modulus), modulus))
+}
+
+/// Where the walk from one start node ends up repeating, counting states as (node, instruction index) pairs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CycleReport {
+    pub start: String,
+    /// Steps taken before the first state that repeats.
+    pub prefix: usize,
+    pub cycle: usize,
+    /// Steps at which a Z node is reached before the cycle starts.
+    pub prefix_hits: Vec<usize>,
+    /// Steps into the cycle at which a Z node is reached.
+    pub offsets: Vec<usize>,
+}
+
+impl CycleReport {
+    fn new(start: &str, input: &Input) -> Result<Self> {
+        let (instructions, map) = input;
+        let mut seen = HashMap::new();
+        let mut hits = vec![];
+        let mut location = start;
+
+        for step in 0.. {
+            let state = (location, step % instructions.len());
+
+            if let Some(&prefix) = seen.get(&state) {
+                let (prefix_hits, cycle_hits): (Vec<_>, Vec<_>) = hits.into_iter().partition(|&hit| hit < prefix);
+
+                return Ok(CycleReport {
+                    start: start.to_string(),
+                    prefix,
+                    cycle: step - prefix,
+                    prefix_hits,
+                    offsets: cycle_hits.into_iter().map(|hit| hit - prefix).collect(),
+                });
+            }
+
+            seen.insert(state, step);
+
+            if location.ends_with('Z') {
+                hits.push(step);
+            }
+
+            let Some((left, right)) = map.get(location) else {
+                bail!("Unknown node: {location}");
+            };
+            location = match instructions[step % instructions.len()] {
+                Instruction::Left => left,
+                Instruction::Right => right,
+            };
+        }
+
+        unreachable!()
+    }
+
+    fn is_hit(&self, step: usize) -> bool {
+        match step.checked_sub(self.prefix) {
+            Some(into_cycle) => self.offsets.contains(&(into_cycle % self.cycle)),
+            None => self.prefix_hits.contains(&step),
+        }
+    }
+
+    pub fn first_hit(&self) -> Option<usize> {
+        self.prefix_hits.first().copied().or_else(|| self.offsets.first().map(|offset| self.prefix + offset))
+    }
+}
+
+pub struct CycleAnalysis {
+    pub reports: Vec<CycleReport>,
+}
+
+impl CycleAnalysis {
+    pub fn new(input: &Input) -> Result<Self> {
+        let (_, map) = input;
+        let reports = map
+            .keys()
+            .filter(|k| k.ends_with('A'))
+            .sorted()
+            .map(|start| CycleReport::new(start, input))
+            .collect::<Result<_>>()?;
+
+        Ok(CycleAnalysis { reports })
+    }
+
+    /// The first step at which every walk is on a Z node at once.
+    pub fn first_common_hit(&self) -> Option<usize> {
+        let longest_prefix = self.reports.iter().map(|report| report.prefix).max()?;
+
+        if let Some(step) = (0..longest_prefix).find(|&step| self.reports.iter().all(|report| report.is_hit(step))) {
+            return Some(step);
+        }
+
+        // Past every prefix, each walk's hits are a set of residues modulo its cycle length.
+        let congruences = self.reports.iter().fold(vec![(0, 1)], |congruences, report| {
+            congruences
+                .iter()
+                .cartesian_product(&report.offsets)
+                .filter_map(|(&congruence, &offset)| {
+                    combine(congruence, ((report.prefix + offset) as i128, report.cycle as i128))
+                })
+                .unique()
+                .collect()
+        });
+
+        congruences
+            .into_iter()
+            .map(|(residue, modulus)| {
+                let behind = (residue - longest_prefix as i128).rem_euclid(modulus);
+                longest_prefix + behind as usize
+            })
+            .min()
+    }
+
+    /// The lcm of the first hits, which is only right when every walk is a clean cycle through a single Z node.
+    pub fn lcm_shortcut(&self) -> Option<usize> {
+        self.reports.iter().map(CycleReport::first_hit).collect::<Option<Vec<_>>>()?.into_iter().reduce(lcm)
+    }
+}
+
 #[aoc(day8, part1)]
-fn part1(input: &Input) -> usize {
+fn part1(input: &Input) -> Result<u64> {
     distance("AAA", "ZZZ", input)
 }
 
 #[aoc(day8, part2)]
-fn part2(input: &Input) -> usize {
-    let (_, map) = input;
+fn part2(input: &Input) -> Result<usize> {
+    match CycleAnalysis::new(input)?.first_common_hit() {
+        Some(steps) => Ok(steps),
+        None => bail!("The walks never reach Z nodes at the same time"),
+    }
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let analysis = CycleAnalysis::new(&parse(input)?)?;
+    let mut explanation = String::new();
+
+    for report in &analysis.reports {
+        writeln!(
+            explanation,
+            "{}: prefix {}, cycle {}, Z before the cycle at {:?}, Z in the cycle at offsets {:?}",
+            report.start, report.prefix, report.cycle, report.prefix_hits, report.offsets,
+        )?;
+    }
 
-    let distances = map
-        .keys()
-        .filter(|k| k.ends_with('A'))
-        .map(|start| distance(start, "Z", input))
-        .collect_vec();
+    let answer = analysis.first_common_hit();
 
-    distances
-        .into_iter()
-        .reduce(lcm)
-        .unwrap()
+    match answer {
+        Some(steps) => writeln!(explanation, "All walks are on Z nodes after {steps} steps")?,
+        None => writeln!(explanation, "The walks never reach Z nodes at the same time")?,
+    }
+
+    if analysis.lcm_shortcut() != answer {
+        writeln!(explanation, "Warning: the lcm of the first hits ({:?}) would have been wrong", analysis.lcm_shortcut())?;
+    }
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -103,26 +364,71 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(2, part1(&parse(include_str!("../test_input/day08.part1.2.txt"))));
+        assert_eq!(2, part1(&parse(include_str!("../test_input/day08.part1.2.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_example2() {
-        assert_eq!(6, part1(&parse(include_str!("../test_input/day08.part1.6.txt"))));
+        assert_eq!(6, part1(&parse(include_str!("../test_input/day08.part1.6.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(21883, part1(&parse(include_str!("../input/2023/day8.txt"))));
+        assert_eq!(21883, part1(&parse(include_str!("../input/2023/day8.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(6, part2(&parse(include_str!("../test_input/day08.part2.6.txt"))));
+        assert_eq!(6, part2(&parse(include_str!("../test_input/day08.part2.6.txt")).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn compiled_network() {
+        let input = parse(include_str!("../test_input/day08.part2.6.txt")).unwrap();
+        let network = Network::compile(&input).unwrap();
+        let start = network.id("22A").unwrap();
+
+        let mut node = start;
+        for steps in 0..50 {
+            assert_eq!(network.name(node), network.name(network.position_after(start, steps)));
+            let (left, right) = &input.1[network.name(node)];
+            node = network.id(if steps % 2 == 0 { left } else { right }).unwrap();
+        }
+
+        assert_eq!("22Z", network.name(network.position_after(start, 1_000_000_000_000_000_002)));
+        assert_eq!(Some(3), network.steps_until(start, |node| network.name(node).ends_with('Z')));
+        assert_eq!(None, network.steps_until(start, |node| network.name(node) == "11Z"));
+        assert_eq!(2, distance("11A", "11Z", &input).unwrap());
+        assert!(distance("11A", "XXX", &input).is_err());
+        assert!(distance("11A", "YYY", &input).is_err());
+    }
+
+    #[test]
+    fn cycle_analysis() {
+        let analysis = CycleAnalysis::new(&parse(include_str!("../test_input/day08.part2.6.txt")).unwrap()).unwrap();
+        assert_eq!(
+            vec![(1, 2, vec![], vec![1]), (1, 6, vec![], vec![2, 5])],
+            analysis.reports.iter().map(|r| (r.prefix, r.cycle, r.prefix_hits.clone(), r.offsets.clone())).collect_vec(),
+        );
+        assert_eq!(Some(6), analysis.first_common_hit());
+        assert_eq!(Some(6), analysis.lcm_shortcut());
+
+        // 11A reaches 11Z after 3 steps and then every 2, 22A reaches 22Z after 4 steps and then every 5.
+        let input = "L\n\n11A = (11B, XXX)\n11B = (11C, XXX)\n11C = (11Z, XXX)\n11Z = (11Y, XXX)\n11Y = (11Z, XXX)\n\
+                     22A = (22B, XXX)\n22B = (22C, XXX)\n22C = (22D, XXX)\n22D = (22Z, XXX)\n22Z = (22E, XXX)\n\
+                     22E = (22F, XXX)\n22F = (22G, XXX)\n22G = (22H, XXX)\n22H = (22Z, XXX)\nXXX = (XXX, XXX)";
+        let analysis = CycleAnalysis::new(&parse(input).unwrap()).unwrap();
+        assert_eq!(Some(9), analysis.first_common_hit());
+        assert_eq!(Some(12), analysis.lcm_shortcut());
+        assert!(explain(input).unwrap().contains("Warning"));
+
+        let input = parse("L\n\n11A = (11B, XXX)\nXXX = (XXX, XXX)").unwrap();
+        assert_eq!("Unknown node: 11B", part2(&input).unwrap_err().to_string());
+        assert!(parse("\n\nXXX = (XXX, XXX)").is_err());
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(12833235391111, part2(&parse(include_str!("../input/2023/day8.txt"))));
+        assert_eq!(12833235391111, part2(&parse(include_str!("../input/2023/day8.txt")).unwrap()).unwrap());
     }
 }
@@ -1,9 +1,10 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use itertools::Itertools;
+use num_integer::Integer;
 
 #[aoc_generator(day9)]
-fn parse(input: &str) -> Result<Vec<Vec<i32>>> {
+pub fn parse(input: &str) -> Result<Vec<Vec<i128>>> {
     input
         .lines()
         .map(|line| line
@@ -14,46 +15,88 @@ fn parse(input: &str) -> Result<Vec<Vec<i32>>> {
         .collect()
 }
 
-fn get_next(numbers: &[i32]) -> Option<i32> {
-    if numbers.len() < 2 {
-        panic!("Too few numbers: {:?}", numbers);
+/// The polynomial of minimal degree through a sequence, in Newton form: `p(x) = Σ C(x, j) Δʲy₀` where `x` is the
+/// index into the sequence.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Extrapolator<T> {
+    /// The leading forward differences `Δʲy₀`, up to the constant one.
+    differences: Vec<T>,
+    len: usize,
+}
+
+impl<T> Extrapolator<T> where T: Integer + Clone + From<i64> {
+    /// Fails unless some row of differences has at least two values, all equal. A single value is not taken as
+    /// constant, since any `n` values fit a polynomial of degree `n - 1` and the prediction would be arbitrary.
+    pub fn fit(values: &[T]) -> Result<Self> {
+        let mut row = values.to_vec();
+        let mut differences = vec![];
+
+        while row.len() >= 2 {
+            differences.push(row[0].clone());
+
+            if row.iter().all_equal() {
+                return Ok(Extrapolator { differences, len: values.len() });
+            }
+
+            row = row.iter().tuple_windows().map(|(a, b)| b.clone() - a.clone()).collect();
+        }
+
+        bail!("The differences of these {} values never become constant", values.len())
+    }
+
+    pub fn degree(&self) -> usize {
+        self.differences.len() - 1
     }
 
-    let diffs = numbers
-        .iter().tuple_windows()
-        .map(|(a, b)| b - a)
-        .collect_vec();
+    /// The value at index `x` of the sequence, which may lie before or after it.
+    pub fn at(&self, x: i64) -> T {
+        let x = T::from(x);
+        let mut binomial = T::one();
+        let mut value = T::zero();
+
+        for (j, difference) in self.differences.iter().enumerate() {
+            if j > 0 {
+                let j = T::from(j as i64);
+                binomial = binomial * (x.clone() - j.clone() + T::one()) / j;
+            }
 
-    if diffs.iter().all_equal() {
-        return Some(numbers.last()? + diffs.first()?)
+            value = value + binomial.clone() * difference.clone();
+        }
+
+        value
+    }
+
+    /// The value `k` steps after the last one.
+    pub fn ahead(&self, k: i64) -> T {
+        self.at(self.len as i64 - 1 + k)
     }
 
-    Some(numbers.last()? + get_next(&diffs)?)
+    /// The value `k` steps before the first one.
+    pub fn behind(&self, k: i64) -> T {
+        self.at(-k)
+    }
 }
 
 #[aoc(day9, part1)]
-fn part1(input: &[Vec<i32>]) -> Option<i32> {
+fn part1(input: &[Vec<i128>]) -> Result<i128> {
     input
         .iter()
-        .map(Vec::as_slice)
-        .map(get_next)
+        .map(|numbers| Ok(Extrapolator::fit(numbers)?.ahead(1)))
         .sum()
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &[Vec<i32>]) -> Option<i32> {
+fn part2(input: &[Vec<i128>]) -> Result<i128> {
     input
         .iter()
-        .map(|numbers| {
-            let rnumbers = numbers.iter().copied().rev().collect_vec();
-            get_next(&rnumbers)
-        })
+        .map(|numbers| Ok(Extrapolator::fit(numbers)?.behind(1)))
         .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use num_bigint::BigInt;
 
     #[test]
     fn part1_example1() {
@@ -65,6 +108,26 @@ mod tests {
         assert_eq!(1916822650, part1(&parse(include_str!("../input/2023/day9.txt")).unwrap()).unwrap());
     }
 
+    #[test]
+    fn extrapolate_any_distance() {
+        let squares = Extrapolator::fit(&[1i128, 4, 9, 16]).unwrap();
+        assert_eq!(2, squares.degree());
+        assert_eq!((25, 10_004_569, 0, 1, 36), (squares.ahead(1), squares.ahead(3_159), squares.behind(1), squares.behind(2), squares.behind(7)));
+
+        let cubes = [10, 11, 12, 13, 14].map(|n: i64| BigInt::from(n).pow(3) * BigInt::from(10).pow(30));
+        let cubes = Extrapolator::fit(&cubes).unwrap();
+        assert_eq!(3, cubes.degree());
+        assert_eq!(BigInt::from(0), cubes.behind(10));
+        assert_eq!(BigInt::from(1_000_000).pow(3) * BigInt::from(10).pow(30), cubes.ahead(999_986));
+
+        assert!(Extrapolator::fit(&[1i128, 2, 4, 8, 16]).is_err());
+        assert!(Extrapolator::fit(&[1i128, 2]).is_err());
+        assert!(Extrapolator::fit(&[7i128]).is_err());
+        assert!(Extrapolator::<i128>::fit(&[]).is_err());
+        assert_eq!(0, Extrapolator::fit(&[7i128, 7]).unwrap().degree());
+        assert_eq!(4, Extrapolator::fit(&[1i128, 2, 3]).unwrap().ahead(1));
+    }
+
     #[test]
     fn part2_example1() {
         assert_eq!(2, part2(&parse(include_str!("../test_input/day09.part2.2.txt")).unwrap()).unwrap());
@@ -1,13 +1,14 @@
 use std::cmp::max;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter, Write};
-use std::iter::successors;
+use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
 use anyhow::{bail, Context, Error, Result};
 use itertools::Itertools;
+use crate::Format;
 
-#[derive(Copy, Clone, Debug)]
-enum Pipe {
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Pipe {
     Vertical,
     Horizontal,
     BendNorthEast,
@@ -29,6 +30,20 @@ impl Display for Pipe {
     }
 }
 
+impl Pipe {
+    /// The offsets of the two neighbors this pipe connects.
+    fn connections(&self) -> [(i32, i32); 2] {
+        match self {
+            Pipe::Vertical => [(-1, 0), (1, 0)],
+            Pipe::Horizontal => [(0, -1), (0, 1)],
+            Pipe::BendNorthEast => [(-1, 0), (0, 1)],
+            Pipe::BendNorthWest => [(-1, 0), (0, -1)],
+            Pipe::BendSouthEast => [(1, 0), (0, 1)],
+            Pipe::BendSouthWest => [(1, 0), (0, -1)],
+        }
+    }
+}
+
 impl TryFrom<char> for Pipe {
     type Error = Error;
 
@@ -47,7 +62,7 @@ impl TryFrom<char> for Pipe {
     }
 }
 
-type Input = ((i32, i32), HashMap<(i32, i32), Pipe>);
+pub type Input = ((i32, i32), HashMap<(i32, i32), Pipe>);
 
 fn determine_starting_pipe(starting_position: &(i32, i32), map: &HashMap<(i32, i32), Pipe>) -> Result<Pipe, Error> {
     match (
@@ -67,7 +82,7 @@ fn determine_starting_pipe(starting_position: &(i32, i32), map: &HashMap<(i32, i
 }
 
 #[aoc_generator(day10)]
-fn parse(input: &str) -> Result<Input> {
+pub fn parse(input: &str) -> Result<Input> {
     let mut map = HashMap::new();
     let mut starting_position = None;
 
@@ -89,29 +104,35 @@ fn parse(input: &str) -> Result<Input> {
     Ok((starting_position, map))
 }
 
-fn step(pos: (i32, i32), ppos: Option<(i32, i32)>, map: &HashMap<(i32, i32), Pipe>) -> ((i32, i32), Option<(i32, i32)>) {
-    match (map.get(&pos), ppos) {
-        (Some(Pipe::Vertical | Pipe::BendSouthEast | Pipe::BendSouthWest), None) => ((pos.0 + 1, pos.1), Some(pos)),
-        (Some(Pipe::Horizontal | Pipe::BendNorthWest), None) => ((pos.0, pos.1 + 1), Some(pos)),
-        (Some(Pipe::BendNorthEast), None) => ((pos.0, pos.1 - 1), Some(pos)),
-        (Some(Pipe::Vertical | Pipe::BendSouthEast | Pipe::BendSouthWest), Some((pj, _))) if pj != pos.0 + 1 => ((pos.0 + 1, pos.1), Some(pos)),
-        (Some(Pipe::Vertical | Pipe::BendNorthEast | Pipe::BendNorthWest), Some((pj, _))) if pj != pos.0 - 1 => ((pos.0 - 1, pos.1), Some(pos)),
-        (Some(Pipe::Horizontal | Pipe::BendNorthWest | Pipe::BendSouthWest), Some((_, pi))) if pi != pos.1 - 1 => ((pos.0, pos.1 - 1), Some(pos)),
-        (Some(Pipe::Horizontal | Pipe::BendNorthEast | Pipe::BendSouthEast), Some((_, pi))) if pi != pos.1 + 1 => ((pos.0, pos.1 + 1), Some(pos)),
-        (pipe, _) => panic!("Nowhere to go from {:?}, coming from {:?}: {:?}", pos, ppos, pipe),
+fn step(pos: (i32, i32), ppos: Option<(i32, i32)>, map: &HashMap<(i32, i32), Pipe>) -> Result<(i32, i32)> {
+    let Some(pipe) = map.get(&pos) else {
+        bail!("Broken loop: no pipe at {pos:?}");
+    };
+    let [a, b] = pipe.connections().map(|(dj, di)| (pos.0 + dj, pos.1 + di));
+
+    match ppos {
+        None => Ok(a),
+        Some(ppos) if ppos == a => Ok(b),
+        Some(ppos) if ppos == b => Ok(a),
+        Some(ppos) => bail!("Broken pipe at {pos:?}: {pipe} does not connect to {ppos:?}"),
     }
 }
 
-fn find_path(starting_position: (i32, i32), map: &HashMap<(i32, i32), Pipe>) -> Vec<(i32, i32)> {
-    successors(Some((starting_position, None)), |(position, previous_position)| {
-        let (position, previous_position) = step(*position, *previous_position, map);
+fn find_path(starting_position: (i32, i32), map: &HashMap<(i32, i32), Pipe>) -> Result<Vec<(i32, i32)>> {
+    let mut path = vec![starting_position];
+    let mut previous_position = starting_position;
+    let mut position = step(starting_position, None, map)?;
 
-        if position == starting_position {
-            return None;
+    while position != starting_position {
+        if path.len() > map.len() {
+            bail!("The loop from {starting_position:?} never closes");
         }
 
-        Some((position, previous_position))
-    }).map(|(pos, _)| pos).collect_vec()
+        path.push(position);
+        (position, previous_position) = (step(position, Some(previous_position), map)?, position);
+    }
+
+    Ok(path)
 }
 
 fn transform_tile(position: (i32, i32), pipe: Pipe) -> [((i32, i32), Pipe); 3] {
@@ -127,7 +148,7 @@ fn transform_tile(position: (i32, i32), pipe: Pipe) -> [((i32, i32), Pipe); 3] {
     }
 }
 
-fn transform_path(path: Vec<(i32, i32)>, map: &HashMap<(i32, i32), Pipe>) -> HashSet<(i32, i32)> {
+fn transform_path(path: &[(i32, i32)], map: &HashMap<(i32, i32), Pipe>) -> HashSet<(i32, i32)> {
     path
         .iter()
         .flat_map(|&position| transform_tile(position, *map.get(&position).unwrap()))
@@ -154,33 +175,222 @@ fn fill(path: &HashSet<(i32, i32)>, height: i32, width: i32) -> HashSet<(i32, i3
     outside
 }
 
-fn count_inside(transformed_path: &HashSet<(i32, i32)>, height: i32, width: i32, outside: &HashSet<(i32, i32)>) -> usize {
+/// The tiles enclosed by the loop, found by flooding the outside of the loop drawn at triple resolution.
+fn inside_tiles(path: &[(i32, i32)], map: &HashMap<(i32, i32), Pipe>) -> HashSet<(i32, i32)> {
+    let path = transform_path(path, map);
+
+    let (height, width) = path
+        .iter()
+        .fold((0, 0), |acc, &pos| (max(acc.0, pos.0 + 2), max(acc.1, pos.1 + 2)));
+
+    let outside = fill(&path, height, width);
+
     (0..height).step_by(3).cartesian_product((0..width).step_by(3))
         .filter(|position| !outside.contains(position))
-        .filter(|position| !transformed_path.contains(position))
-        .count()
+        .filter(|position| !path.contains(position))
+        .map(|(j, i)| (j / 3, i / 3))
+        .collect()
 }
 
-#[aoc(day10, part1)]
-fn part1(input: &Input) -> usize {
+/// Pick's theorem, `A = i + b/2 - 1`, solved for the number of interior tiles `i`, with the area `A` of the polygon
+/// through the tile centers from the shoelace formula.
+fn shoelace_area(path: &[(i32, i32)]) -> usize {
+    let twice_area = path
+        .iter()
+        .circular_tuple_windows()
+        .map(|(&(j1, i1), &(j2, i2))| i1 as i64 * j2 as i64 - i2 as i64 * j1 as i64)
+        .sum::<i64>()
+        .unsigned_abs() as usize;
+
+    (twice_area + 2 - path.len()) / 2
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum AreaMethod {
+    FloodFill,
+    #[default]
+    Shoelace,
+    /// Both, failing if they disagree.
+    CrossCheck,
+}
+
+impl FromStr for AreaMethod {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "flood-fill" => Ok(AreaMethod::FloodFill),
+            "shoelace" => Ok(AreaMethod::Shoelace),
+            "cross-check" => Ok(AreaMethod::CrossCheck),
+            _ => bail!("Unknown area method: {s}"),
+        }
+    }
+}
+
+/// The number of tiles enclosed by the loop.
+pub fn enclosed_tiles(input: &Input, method: AreaMethod) -> Result<usize> {
     let (starting_position, map) = input;
-    let path = find_path(*starting_position, map);
+    let path = find_path(*starting_position, map)?;
+
+    match method {
+        AreaMethod::FloodFill => Ok(inside_tiles(&path, map).len()),
+        AreaMethod::Shoelace => Ok(shoelace_area(&path)),
+        AreaMethod::CrossCheck => match (inside_tiles(&path, map).len(), shoelace_area(&path)) {
+            (flood_fill, shoelace) if flood_fill == shoelace => Ok(shoelace),
+            (flood_fill, shoelace) => bail!("Flood fill found {flood_fill} enclosed tiles, but the shoelace formula {shoelace}"),
+        },
+    }
+}
 
-    path.len() / 2
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Region {
+    Loop,
+    Inside,
+    Outside,
 }
 
-#[aoc(day10, part2)]
-fn part2(input: &Input) -> usize {
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Tile {
+    pub pipe: Option<Pipe>,
+    pub region: Region,
+}
+
+/// Every tile of a `height` by `width` map, row by row, with the starting position showing its inferred pipe.
+pub fn classify(input: &Input, height: usize, width: usize) -> Result<Vec<Vec<Tile>>> {
     let (starting_position, map) = input;
-    let path = transform_path(find_path(*starting_position, map), map);
+    let path = find_path(*starting_position, map)?;
+    let on_loop: HashSet<_> = path.iter().copied().collect();
+    let inside = inside_tiles(&path, map);
+
+    Ok(
+        (0..height as i32)
+            .map(|j| {
+                (0..width as i32)
+                    .map(|i| Tile {
+                        pipe: map.get(&(j, i)).copied(),
+                        region: match (on_loop.contains(&(j, i)), inside.contains(&(j, i))) {
+                            (true, _) => Region::Loop,
+                            (false, true) => Region::Inside,
+                            (false, false) => Region::Outside,
+                        },
+                    })
+                    .collect()
+            })
+            .collect()
+    )
+}
 
-    let (height, width) = path
-        .iter()
-        .fold((0, 0), |acc, &pos| (max(acc.0, pos.0 + 2), max(acc.1, pos.1 + 2)));
+impl Tile {
+    fn ansi(&self) -> &'static str {
+        match (self.region, self.pipe) {
+            (Region::Loop, _) => "\x1b[0;1;33m",
+            (Region::Inside, Some(_)) => "\x1b[0;2;42m",
+            (Region::Inside, None) => "\x1b[0;42m",
+            (Region::Outside, Some(_)) => "\x1b[0;2;44m",
+            (Region::Outside, None) => "\x1b[0;44m",
+        }
+    }
 
-    let outside = fill(&path, height, width);
+    fn fill(&self) -> &'static str {
+        match self.region {
+            Region::Loop => "#0f0f23",
+            Region::Inside => "#2e7d32",
+            Region::Outside => "#1a237e",
+        }
+    }
+}
+
+/// The maze with ANSI colors: the loop bold yellow, tiles inside it on green and outside it on blue, with the pipes
+/// that are not part of the loop dimmed.
+pub fn render_ansi(tiles: &[Vec<Tile>]) -> String {
+    let mut output = String::new();
 
-    count_inside(&path, height, width, &outside)
+    for row in tiles {
+        let mut previous = None;
+
+        for tile in row {
+            if previous != Some(tile.ansi()) {
+                output.push_str(tile.ansi());
+                previous = Some(tile.ansi());
+            }
+
+            match tile.pipe {
+                Some(pipe) => output.push_str(&pipe.to_string()),
+                None => output.push('.'),
+            }
+        }
+
+        output.push_str("\x1b[0m\n");
+    }
+
+    output
+}
+
+/// A standalone SVG image with the same shading as [`render_ansi`], with each pipe drawn as lines from the center of
+/// its tile to the sides it connects.
+pub fn render_svg(tiles: &[Vec<Tile>]) -> String {
+    const SIZE: usize = 10;
+    let height = tiles.len() * SIZE;
+    let width = tiles.iter().map(Vec::len).max().unwrap_or(0) * SIZE;
+    let mut body = String::new();
+
+    for (j, row) in tiles.iter().enumerate() {
+        for (i, tile) in row.iter().enumerate() {
+            let (y, x) = (j * SIZE, i * SIZE);
+            body.push_str(&format!(r#"<rect x="{x}" y="{y}" width="{SIZE}" height="{SIZE}" fill="{}"/>"#, tile.fill()));
+            body.push('\n');
+
+            if let Some(pipe) = tile.pipe {
+                let class = match tile.region {
+                    Region::Loop => "loop",
+                    _ => "junk",
+                };
+                let (cy, cx) = (y + SIZE / 2, x + SIZE / 2);
+                let d = pipe
+                    .connections()
+                    .iter()
+                    .map(|&(dj, di)| format!("M{cx} {cy}l{} {}", di * SIZE as i32 / 2, dj * SIZE as i32 / 2))
+                    .join("");
+
+                body.push_str(&format!(r#"<path class="{class}" d="{d}"/>"#));
+                body.push('\n');
+            }
+        }
+    }
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+<style>
+.loop {{ stroke: #ffff66; stroke-width: 3; stroke-linecap: square; }}
+.junk {{ stroke: #777777; stroke-width: 1; }}
+</style>
+{body}</svg>
+"#
+    )
+}
+
+pub(crate) fn render(input: &str, format: Format) -> Result<String> {
+    let width = input.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    let tiles = classify(&parse(input)?, input.lines().count(), width)?;
+
+    match format {
+        Format::Ansi => Ok(render_ansi(&tiles)),
+        Format::Svg => Ok(render_svg(&tiles)),
+        Format::Html => bail!("Day 10 cannot be rendered as HTML"),
+    }
+}
+
+#[aoc(day10, part1)]
+fn part1(input: &Input) -> Result<usize> {
+    let (starting_position, map) = input;
+    let path = find_path(*starting_position, map)?;
+
+    Ok(path.len() / 2)
+}
+
+#[aoc(day10, part2)]
+fn part2(input: &Input) -> Result<usize> {
+    enclosed_tiles(input, AreaMethod::default())
 }
 
 #[cfg(test)]
@@ -189,36 +399,83 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(4, part1(&parse(include_str!("../test_input/day10.part1.4.txt")).unwrap()));
+        assert_eq!(4, part1(&parse(include_str!("../test_input/day10.part1.4.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_example2() {
-        assert_eq!(8, part1(&parse(include_str!("../test_input/day10.part1.8.txt")).unwrap()));
+        assert_eq!(8, part1(&parse(include_str!("../test_input/day10.part1.8.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(6882, part1(&parse(include_str!("../input/2023/day10.txt")).unwrap()));
+        assert_eq!(6882, part1(&parse(include_str!("../input/2023/day10.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(4, part2(&parse(include_str!("../test_input/day10.part2.4.txt")).unwrap()));
+        assert_eq!(4, part2(&parse(include_str!("../test_input/day10.part2.4.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_example2() {
-        assert_eq!(8, part2(&parse(include_str!("../test_input/day10.part2.8.txt")).unwrap()));
+        assert_eq!(8, part2(&parse(include_str!("../test_input/day10.part2.8.txt")).unwrap()).unwrap());
     }
 
     #[test]
     fn part2_example3() {
-        assert_eq!(10, part2(&parse(include_str!("../test_input/day10.part2.10.txt")).unwrap()));
+        assert_eq!(10, part2(&parse(include_str!("../test_input/day10.part2.10.txt")).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn area_methods_agree() {
+        for input in [
+            include_str!("../test_input/day10.part2.4.txt"),
+            include_str!("../test_input/day10.part2.8.txt"),
+            include_str!("../test_input/day10.part2.10.txt"),
+        ] {
+            let input = parse(input).unwrap();
+            assert_eq!(enclosed_tiles(&input, AreaMethod::FloodFill).unwrap(), enclosed_tiles(&input, AreaMethod::CrossCheck).unwrap());
+        }
+    }
+
+    #[test]
+    fn nothing_enclosed() {
+        let input = parse("S7\nLJ").unwrap();
+
+        for method in [AreaMethod::FloodFill, AreaMethod::Shoelace, AreaMethod::CrossCheck] {
+            assert_eq!(0, enclosed_tiles(&input, method).unwrap());
+        }
+    }
+
+    #[test]
+    fn broken_pipes() {
+        let mut input = parse(include_str!("../test_input/day10.part1.4.txt")).unwrap();
+        input.1.insert((3, 2), Pipe::Vertical);
+        assert!(part1(&input).unwrap_err().to_string().starts_with("Broken pipe at (3, 2)"));
+
+        input.1.remove(&(3, 2));
+        assert!(part1(&input).unwrap_err().to_string().starts_with("Broken loop: no pipe at (3, 2)"));
+    }
+
+    #[test]
+    fn render_regions() {
+        let input = include_str!("../test_input/day10.part2.4.txt");
+        let tiles = classify(&parse(input).unwrap(), 9, 11).unwrap();
+        let ansi = render_ansi(&tiles);
+        let svg = render_svg(&tiles);
+
+        assert_eq!(4, tiles.iter().flatten().filter(|tile| tile.region == Region::Inside).count());
+        assert!(ansi.starts_with("\x1b[0;44m...........\x1b[0m\n\x1b[0;44m.\x1b[0;1;33m┌───────┐\x1b[0;44m.\x1b[0m\n"));
+        assert!(ansi.contains("\x1b[0;1;33m││\x1b[0;44m.....\x1b[0;1;33m││"));
+        assert!(ansi.contains("\x1b[0;1;33m│\x1b[0;42m..\x1b[0;1;33m│"));
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="110" height="90""#));
+        assert_eq!(46, svg.matches(r#"<path class="loop""#).count());
+        assert!(render(input, Format::Html).is_err());
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(491, part2(&parse(include_str!("../input/2023/day10.txt")).unwrap()));
+        assert_eq!(491, part2(&parse(include_str!("../input/2023/day10.txt")).unwrap()).unwrap());
     }
 }
@@ -1,8 +1,10 @@
 use std::cmp::min;
 use std::collections::HashMap;
+use std::fmt::Write;
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::{bail, Context, Error, Result};
+use anyhow::{bail, Error, Result};
 use itertools::Itertools;
+use crate::parser::{grid, Span};
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum Tile {
@@ -34,23 +36,9 @@ type Input = Vec<(usize, usize, HashMap<(usize, usize), Tile>)>;
 
 #[aoc_generator(day13)]
 fn parse(input: &str) -> Result<Input> {
-    input
-        .split("\n\n")
-        .map(|pattern| {
-            let height = pattern.lines().count();
-            let width = pattern.lines().next().context("Unexpected empty line")?.len();
-            let pattern = pattern
-                .lines()
-                .enumerate()
-                .flat_map(|(j, line)| {
-                    line.chars()
-                        .enumerate()
-                        .map(move |(i, c)| Ok(((j, i), Tile::try_from(c)?)))
-                })
-                .collect::<Result<HashMap<(usize, usize), Tile>>>();
-
-            Ok((height, width, pattern?))
-        })
+    Span::new(input)
+        .sections()
+        .map(|pattern| grid(pattern, |c| Tile::try_from(c).map(Some)))
         .collect()
 }
 
@@ -101,26 +89,53 @@ fn part2(input: &Input) -> Option<usize> {
     input
         .iter()
         .map(|(height, width, pattern)| {
-            let initial_mirror = *find_mirrors(*height, *width, pattern).first()?;
+            match find_smudge(*height, *width, pattern)? {
+                (_, (Orientation::Vertical, k)) => Some(k),
+                (_, (Orientation::Horizontal, k)) => Some(k * 100),
+            }
+        })
+        .sum()
+}
 
-            for (&(j, i), tile) in pattern {
-                let mut modified_pattern = pattern.clone();
+/// The position of the smudge and the new reflection line it uncovers.
+fn find_smudge(height: usize, width: usize, pattern: &HashMap<(usize, usize), Tile>) -> Option<((usize, usize), (Orientation, usize))> {
+    let initial_mirror = *find_mirrors(height, width, pattern).first()?;
 
-                modified_pattern.insert((j, i), match tile {
-                    Tile::Ash => Tile::Rock,
-                    Tile::Rock => Tile::Ash,
-                });
+    for (&(j, i), tile) in pattern.iter().sorted_by_key(|&(&position, _)| position) {
+        let mut modified_pattern = pattern.clone();
 
-                match find_mirrors(*height, *width, &modified_pattern).iter().find(|&&mirror| mirror != initial_mirror) {
-                    None => {}
-                    Some(&(Orientation::Vertical, k)) => { return Some(k) },
-                    Some(&(Orientation::Horizontal, k)) => { return Some(k * 100) },
-                }
-            }
+        modified_pattern.insert((j, i), match tile {
+            Tile::Ash => Tile::Rock,
+            Tile::Rock => Tile::Ash,
+        });
 
-            None
-        })
-        .sum()
+        if let Some(&mirror) = find_mirrors(height, width, &modified_pattern).iter().find(|&&mirror| mirror != initial_mirror) {
+            return Some(((j, i), mirror));
+        }
+    }
+
+    None
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let mut explanation = String::new();
+
+    for (n, (height, width, pattern)) in parse(input)?.iter().enumerate() {
+        let mirror = find_mirrors(*height, *width, pattern).first().copied();
+        let smudge = find_smudge(*height, *width, pattern);
+
+        match mirror {
+            Some((orientation, k)) => write!(explanation, "Pattern {n}: {orientation:?} reflection at {k}")?,
+            None => write!(explanation, "Pattern {n}: no reflection")?,
+        }
+
+        match smudge {
+            Some(((j, i), (orientation, k))) => writeln!(explanation, "; smudge at ({j}, {i}) gives {orientation:?} reflection at {k}")?,
+            None => writeln!(explanation, "; no smudge found")?,
+        }
+    }
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -142,6 +157,13 @@ mod tests {
         assert_eq!(400, part2(&parse(include_str!("../test_input/day13.part2.400.txt")).unwrap()).unwrap());
     }
 
+    #[test]
+    fn explain_example1() {
+        let explanation = explain(include_str!("../test_input/day13.part2.400.txt")).unwrap();
+        assert!(explanation.contains("Pattern 0: Vertical reflection at 5; smudge at (0, 0) gives Horizontal reflection at 3"));
+        assert!(explanation.contains("Pattern 1: Horizontal reflection at 4; smudge at (0, 4) gives Horizontal reflection at 1"));
+    }
+
     #[test]
     fn part2_input() {
         assert_eq!(34224, part2(&parse(include_str!("../input/2023/day13.txt")).unwrap()).unwrap());
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use aoc_runner_derive::{aoc, aoc_generator};
-use anyhow::{bail, Context, Error, Result};
+use anyhow::{bail, Error, Result};
 use itertools::Itertools;
+use crate::parser::{grid, Span};
 
 #[derive(Eq, PartialEq, Copy, Clone, Hash)]
 enum Rock {
@@ -27,20 +28,10 @@ type Input = (usize, usize, HashMap<(usize, usize), Rock>);
 
 #[aoc_generator(day14)]
 fn parse(input: &str) -> Result<Input> {
-        let height = input.lines().count();
-        let width = input.lines().next().context("Unexpected empty line")?.len();
-        let map = input
-            .lines()
-            .enumerate()
-            .flat_map(|(j, line)| {
-                line.chars()
-                    .enumerate()
-                    .filter(|&(_, c)| c != '.')
-                    .map(move |(i, c)| Ok(((j, i), Rock::try_from(c)?)))
-            })
-            .collect::<Result<HashMap<(usize, usize), Rock>>>();
-
-        Ok((height, width, map?))
+    grid(Span::new(input), |c| match c {
+        '.' => Ok(None),
+        _ => Rock::try_from(c).map(Some),
+    })
 }
 
 fn tilt_north(mut map: HashMap<(usize, usize), Rock>) -> HashMap<(usize, usize), Rock> {
@@ -75,8 +66,9 @@ fn part1(input: &Input) -> usize {
         .sum()
 }
 
-#[aoc(day14, part2)]
-fn part2(input: &Input) -> usize {
+/// Spins until the platform repeats, returning the iteration where the cycle starts, its length, and the load after
+/// each iteration so far.
+fn find_cycle(input: &Input) -> (usize, usize, Vec<usize>) {
     let (height, width, mut map) = input.clone();
     let mut hashes = HashMap::new();
     let mut loads = vec![];
@@ -90,11 +82,8 @@ fn part2(input: &Input) -> usize {
                 .collect_vec()
         );
 
-        if let Some(cycle_start) = hashes.get(&hash) {
-            let cycle_length = iteration - cycle_start;
-            let remainder = (1000000000 - cycle_start) % cycle_length;
-
-            return loads[cycle_start + remainder];
+        if let Some(&cycle_start) = hashes.get(&hash) {
+            return (cycle_start, iteration - cycle_start, loads);
         }
 
         let load: usize = map.iter().filter(|&(_, &rock)| rock == Rock::Round).map(|((j, _), _)| height - j).sum();
@@ -110,6 +99,23 @@ fn part2(input: &Input) -> usize {
     unreachable!()
 }
 
+#[aoc(day14, part2)]
+fn part2(input: &Input) -> usize {
+    let (cycle_start, cycle_length, loads) = find_cycle(input);
+    let remainder = (1000000000 - cycle_start) % cycle_length;
+
+    loads[cycle_start + remainder]
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let (cycle_start, cycle_length, loads) = find_cycle(&parse(input)?);
+
+    Ok(format!(
+        "Cycle starts after {cycle_start} spin cycles and has length {cycle_length}; loads in cycle: {}\n",
+        loads[cycle_start..].iter().join(", "),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +135,12 @@ mod tests {
         assert_eq!(64, part2(&parse(include_str!("../test_input/day14.part2.64.txt")).unwrap()));
     }
 
+    #[test]
+    fn explain_example1() {
+        let explanation = explain(include_str!("../test_input/day14.part2.64.txt")).unwrap();
+        assert!(explanation.starts_with("Cycle starts after 3 spin cycles and has length 7;"));
+    }
+
     #[test]
     fn part2_input() {
         assert_eq!(106689, part2(&parse(include_str!("../input/2023/day14.txt")).unwrap()));
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
 use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
 use strum_macros::EnumString;
-use anyhow::{bail, Context, Error, Result};
+use anyhow::{bail, Error, Result};
+use crate::parser::Span;
 
 #[derive(Copy, Clone, EnumString)]
 #[strum(ascii_case_insensitive)]
@@ -45,28 +47,26 @@ enum Rule {
     Unconditional(Target),
 }
 
-impl FromStr for Rule {
-    type Err = Error;
+impl TryFrom<Span<'_>> for Rule {
+    type Error = Error;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn try_from(s: Span) -> Result<Self> {
         if let Ok(target) = s.parse() {
             return Ok(Rule::Unconditional(target));
         }
 
-        let category = s[0..1].parse()?;
-        let operator = &s[1..2];
-        let colon_pos = s.chars().position(|c| c == ':').context(format!("Invalid rule; no colon: {s}"))?;
-        let value = s[2..colon_pos].parse()?;
-        let target = s[(colon_pos + 1)..].parse()?;
+        let (condition, target) = s.split_once(":")?;
+        let target = target.parse()?;
 
-        Ok(
-           match operator {
-               ">" => Rule::GreaterThan(category, value, target),
-               "<" => Rule::LessThan(category, value, target),
-               _ => bail!("Invalid rule; unknown operator: {s}"),
-           }
+        if let Ok((category, value)) = condition.split_once(">") {
+            return Ok(Rule::GreaterThan(category.parse()?, value.parse()?, target));
+        }
 
-        )
+        if let Ok((category, value)) = condition.split_once("<") {
+            return Ok(Rule::LessThan(category.parse()?, value.parse()?, target));
+        }
+
+        Err(condition.error(format!("Invalid rule; unknown operator: {s}")))
     }
 }
 
@@ -80,17 +80,17 @@ struct Part {
     s: usize,
 }
 
-impl FromStr for Part {
-    type Err = Error;
+impl TryFrom<Span<'_>> for Part {
+    type Error = Error;
 
-    fn from_str(r: &str) -> Result<Self> {
+    fn try_from(r: Span) -> Result<Self> {
         let (mut x, mut m, mut a, mut s) = (None, None, None, None);
 
-        for rating in (r[1..(r.len() - 1)]).split(',') {
-            let category = rating[0..1].parse()?;
-            let value = rating[2..].parse()?;
+        for rating in r.strip_prefix("{")?.strip_suffix("}")?.split(",") {
+            let (category, value) = rating.split_once("=")?;
+            let value = value.parse()?;
 
-            match category {
+            match category.parse()? {
                 Category::X => { x = Some(value); },
                 Category::M => { m = Some(value); },
                 Category::A => { a = Some(value); },
@@ -99,10 +99,10 @@ impl FromStr for Part {
         }
 
         Ok(Part {
-            x: x.context(format!("Invalid part; x rating missing: {r}"))?,
-            m: m.context(format!("Invalid part; m rating missing: {r}"))?,
-            a: a.context(format!("Invalid part; a rating missing: {r}"))?,
-            s: s.context(format!("Invalid part; s rating missing: {r}"))?,
+            x: x.ok_or_else(|| r.error(format!("Invalid part; x rating missing: {r}")))?,
+            m: m.ok_or_else(|| r.error(format!("Invalid part; m rating missing: {r}")))?,
+            a: a.ok_or_else(|| r.error(format!("Invalid part; a rating missing: {r}")))?,
+            s: s.ok_or_else(|| r.error(format!("Invalid part; s rating missing: {r}")))?,
         })
 
     }
@@ -123,25 +123,25 @@ type Input = (HashMap<String, Workflow>, Vec<Part>);
 
 #[aoc_generator(day19)]
 fn parse(input: &str) -> Result<Input> {
-    let (workflows, parts) = input.split_once("\n\n").context("Invalid input")?;
+    let (workflows, parts) = Span::new(input).split_once("\n\n")?;
 
     let workflows = workflows
         .lines()
         .map(|workflow| {
-            let curly_pos = workflow.chars().position(|c| c == '{').context(format!("Invalid workflow; no curly bracket: {workflow}"))?;
-            let name = workflow[0..curly_pos].to_string();
-            let rules = workflow[(curly_pos + 1)..(workflow.len() - 1)]
-                .split(',')
-                .map(str::parse)
+            let (name, rules) = workflow.split_once("{")?;
+            let rules = rules
+                .strip_suffix("}")?
+                .split(",")
+                .map(Rule::try_from)
                 .collect::<Result<_>>()?;
 
-            Ok((name, rules))
+            Ok((name.to_string(), rules))
         })
         .collect::<Result<_>>()?;
 
     let parts = parts
         .lines()
-        .map(str::parse)
+        .map(Part::try_from)
         .collect::<Result<_>>()?;
 
     Ok((workflows, parts))
@@ -221,39 +221,57 @@ fn set_upper_bounds(mut bounds: Bounds, category: Category, value: usize) -> Bou
     bounds
 }
 
-fn target_combinations(workflows: &HashMap<String, Workflow>, target: &Target, bounds: Bounds) -> usize {
+impl Bounds {
+    fn combinations(&self) -> usize {
+        [self.x, self.m, self.a, self.s]
+            .iter()
+            .map(|(lower, upper)| upper.saturating_sub(*lower))
+            .product()
+    }
+}
+
+impl Display for Bounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "x {}..={}, m {}..={}, a {}..={}, s {}..={}",
+            self.x.0, self.x.1 - 1, self.m.0, self.m.1 - 1, self.a.0, self.a.1 - 1, self.s.0, self.s.1 - 1,
+        )
+    }
+}
+
+fn target_accepted(workflows: &HashMap<String, Workflow>, target: &Target, bounds: Bounds) -> Vec<Bounds> {
     match target {
-        Target::Accept => (bounds.x.1 - bounds.x.0) * (bounds.m.1 - bounds.m.0) * (bounds.a.1 - bounds.a.0) * (bounds.s.1 - bounds.s.0),
-        Target::Reject => 0,
-        Target::Redirect(workflow_name) => workflow_combinations(workflows, workflows.get(workflow_name).unwrap(), bounds),
+        Target::Accept => vec![bounds],
+        Target::Reject => vec![],
+        Target::Redirect(workflow_name) => workflow_accepted(workflows, workflows.get(workflow_name).unwrap(), bounds),
     }
 }
 
-fn workflow_combinations(workflows: &HashMap<String, Workflow>, workflow: &Workflow, mut bounds: Bounds) -> usize {
-    let mut num_combinations = 0;
+fn workflow_accepted(workflows: &HashMap<String, Workflow>, workflow: &Workflow, mut bounds: Bounds) -> Vec<Bounds> {
+    let mut accepted = vec![];
 
     for rule in workflow {
         match rule {
             Rule::GreaterThan(c, v, t) => {
-                num_combinations += target_combinations(workflows, t, set_lower_bounds(bounds, *c, *v + 1));
+                accepted.extend(target_accepted(workflows, t, set_lower_bounds(bounds, *c, *v + 1)));
                 bounds = set_upper_bounds(bounds, *c, *v + 1);
             },
             Rule::LessThan(c, v, t) => {
-                num_combinations += target_combinations(workflows, t, set_upper_bounds(bounds, *c, *v));
+                accepted.extend(target_accepted(workflows, t, set_upper_bounds(bounds, *c, *v)));
                 bounds = set_lower_bounds(bounds, *c, *v);
             },
             Rule::Unconditional(t) => {
-                num_combinations += target_combinations(workflows, t, bounds);
+                accepted.extend(target_accepted(workflows, t, bounds));
                 break;
             },
         }
     }
 
-    num_combinations
+    accepted
 }
 
-#[aoc(day19, part2)]
-fn part2((workflows, _): &Input) -> usize {
+fn accepted_bounds(workflows: &HashMap<String, Workflow>) -> Vec<Bounds> {
     let bounds = Bounds{
         x: (1, 4001),
         m: (1, 4001),
@@ -261,7 +279,26 @@ fn part2((workflows, _): &Input) -> usize {
         s: (1, 4001),
     };
 
-    workflow_combinations(workflows, workflows.get("in").unwrap(), bounds)
+    workflow_accepted(workflows, workflows.get("in").unwrap(), bounds)
+}
+
+#[aoc(day19, part2)]
+fn part2((workflows, _): &Input) -> usize {
+    accepted_bounds(workflows)
+        .iter()
+        .map(Bounds::combinations)
+        .sum()
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let (workflows, _) = parse(input)?;
+    let mut explanation = String::new();
+
+    for bounds in accepted_bounds(&workflows) {
+        writeln!(explanation, "{bounds}: {} combinations", bounds.combinations())?;
+    }
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -283,6 +320,12 @@ mod tests {
         assert_eq!(167409079868000, part2(&parse(include_str!("../test_input/day19.part2.167409079868000.txt")).unwrap()));
     }
 
+    #[test]
+    fn explain_example1() {
+        let explanation = explain(include_str!("../test_input/day19.part2.167409079868000.txt")).unwrap();
+        assert!(explanation.contains("x 1..=1415, m 1..=4000, a 1..=2005, s 1..=1350: 15320205000000 combinations"));
+    }
+
     #[test]
     fn part2_input() {
         assert_eq!(121964982771486, part2(&parse(include_str!("../input/2023/day19.txt")).unwrap()));
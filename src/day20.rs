@@ -1,8 +1,9 @@
 use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
 use aoc_runner_derive::{aoc, aoc_generator};
-use regex::Regex;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use itertools::Itertools;
+use crate::parser::Span;
 
 #[derive(Clone)]
 enum Module {
@@ -28,14 +29,20 @@ type Input = HashMap<String, Module>;
 
 #[aoc_generator(day20)]
 fn parse(input: &str) -> Result<Input> {
-    let pattern = Regex::new(r"^([%&]?)([a-z]+) -> (.*)$")?;
-
-    let modules = input
+    let modules = Span::new(input)
         .lines()
         .map(|line| {
-            let caps = pattern.captures(line).context(format!("Invalid input: {line}"))?;
-            let (_, [type_indicator, name, targets]) = caps.extract();
-            let targets = targets.split(", ").map(str::to_string).collect_vec();
+            let (module, targets) = line.split_once(" -> ")?;
+            let (type_indicator, name) = match module.as_str().chars().next() {
+                Some('%' | '&') => module.as_str().split_at(1),
+                _ => ("", module.as_str()),
+            };
+
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase()) {
+                return Err(module.error(format!("Invalid module: {module}")));
+            }
+
+            let targets = targets.split(", ").map(|target| target.to_string()).collect_vec();
 
             Ok((type_indicator, name, targets))
         })
@@ -62,7 +69,7 @@ fn parse(input: &str) -> Result<Input> {
                     "&" => (
                         name.to_string(),
                         Module::Conjunction(
-                            sources.get(name).unwrap().iter().map(|source| (source.to_string(), Pulse::Low)).collect(),
+                            sources.get(name).into_iter().flatten().map(|source| (source.to_string(), Pulse::Low)).collect(),
                             targets.clone(),
                         ),
                     ),
@@ -158,24 +165,25 @@ fn part1(modules: &Input) -> usize {
     count_high * count_low
 }
 
-#[aoc(day20, part2)]
-fn part2(modules: &Input) -> usize {
+/// How many button presses [`rx_feeder_cycles`] waits for every feeder to send a high pulse.
+const MAX_PRESSES: usize = 100_000;
+
+/// The number of button presses after which each module feeding the conjunction in front of `rx` first sends a high
+/// pulse.
+fn rx_feeder_cycles(modules: &Input) -> Result<HashMap<String, usize>> {
     let mut modules = modules.clone();
 
     let mut rx_source_cycles = modules
-        .iter()
-        .find(|(_, module)| match module {
-            Module::Conjunction(_, targets) => targets.contains(&"rx".to_string()),
-            _ => false,
-        })
-        .map(|(_, module)| match module {
-            Module::Conjunction(sources, _) => sources.keys().map(|source| (source.to_string(), None)),
-            _ => unimplemented!(),
+        .values()
+        .find_map(|module| match module {
+            Module::Conjunction(sources, targets) if targets.contains(&"rx".to_string()) => {
+                Some(sources.keys().map(|source| (source.to_string(), None)).collect::<HashMap<_, _>>())
+            },
+            _ => None,
         })
-        .unwrap()
-        .collect::<HashMap<_, _>>();
+        .context("No conjunction module feeds rx")?;
 
-    for button_presses in 1.. {
+    for button_presses in 1..=MAX_PRESSES {
         for (source, _, pulse) in simulate(&mut modules) {
             for (rx_source, cycle_count) in &mut rx_source_cycles {
                 if cycle_count.is_none() && &source == rx_source && pulse == Pulse::High {
@@ -190,11 +198,34 @@ fn part2(modules: &Input) -> usize {
     }
 
     rx_source_cycles
-        .values()
-        .copied()
-        .map(Option::unwrap)
+        .into_iter()
+        .sorted()
+        .map(|(rx_source, cycle_count)| match cycle_count {
+            Some(cycle_count) => Ok((rx_source, cycle_count)),
+            None => bail!("{rx_source} sent no high pulse within {MAX_PRESSES} button presses"),
+        })
+        .collect()
+}
+
+#[aoc(day20, part2)]
+fn part2(modules: &Input) -> Result<usize> {
+    rx_feeder_cycles(modules)?
+        .into_values()
         .reduce(lcm)
-        .unwrap()
+        .context("Nothing feeds the conjunction in front of rx")
+}
+
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let cycles = rx_feeder_cycles(&parse(input)?)?;
+    let mut explanation = String::new();
+
+    for (feeder, cycle_length) in cycles.iter().sorted() {
+        writeln!(explanation, "{feeder}: high pulse every {cycle_length} button presses")?;
+    }
+
+    writeln!(explanation, "lcm: {}", cycles.into_values().reduce(lcm).unwrap_or_default())?;
+
+    Ok(explanation)
 }
 
 #[cfg(test)]
@@ -216,8 +247,29 @@ mod tests {
         assert_eq!(856482136, part1(&parse(include_str!("../input/2023/day20.txt")).unwrap()));
     }
 
+    #[test]
+    fn explain_example2() {
+        assert!(explain(include_str!("../test_input/day20.part1.11687500.txt")).is_err());
+    }
+
+    #[test]
+    fn explain_feeder_cycles() {
+        // p and q count button presses in binary, so inv first sends a high pulse once both are on.
+        let input = "broadcaster -> p, r\n%p -> q, c\n%q -> c\n&c -> inv\n&inv -> hub\n%r -> s\n%s -> hub\n&hub -> rx";
+        assert_eq!(
+            "inv: high pulse every 3 button presses\ns: high pulse every 2 button presses\nlcm: 6\n",
+            explain(input).unwrap(),
+        );
+
+        assert_eq!("No conjunction module feeds rx", explain("broadcaster -> a\n%a -> rx").unwrap_err().to_string());
+        assert_eq!(
+            "c sent no high pulse within 100000 button presses",
+            explain("broadcaster -> a\n%a -> hub\n&hub -> rx\n%c -> hub\n&d -> c").unwrap_err().to_string(),
+        );
+    }
+
     #[test]
     fn part2_input() {
-        assert_eq!(224046542165867, part2(&parse(include_str!("../input/2023/day20.txt")).unwrap()));
+        assert_eq!(224046542165867, part2(&parse(include_str!("../input/2023/day20.txt")).unwrap()).unwrap());
     }
 }
@@ -1,16 +1,25 @@
 use std::ops::{Add, Mul};
 use aoc_runner_derive::{aoc, aoc_generator};
+use anyhow::Result;
 use itertools::Itertools;
 use nalgebra::{Matrix2, Vector2};
 use z3::ast::{Ast, Int};
+use crate::parser::{numbers, Span};
 
 type Input = Vec<(i64, i64, i64, i64, i64, i64)>;
 
 #[aoc_generator(day24)]
-fn parse(input: &str) -> Input {
-    input
+fn parse(input: &str) -> Result<Input> {
+    Span::new(input)
         .lines()
-        .flat_map(|line| line.split(&[',', ' ', '@']).filter_map(|s| s.parse::<i64>().ok()).tuples())
+        .map(|line| {
+            let (position, velocity) = line.split_once("@")?;
+
+            match (&numbers::<i64>(position)?[..], &numbers::<i64>(velocity)?[..]) {
+                (&[px, py, pz], &[vx, vy, vz]) => Ok((px, py, pz, vx, vy, vz)),
+                _ => Err(line.error(format!("Expected three coordinates and three velocities: {line}"))),
+            }
+        })
         .collect()
 }
 
@@ -82,21 +91,21 @@ mod tests {
 
     #[test]
     fn part1_example1() {
-        assert_eq!(2, solve1(&parse(include_str!("../test_input/day24.example1.txt")), (7, 27)));
+        assert_eq!(2, solve1(&parse(include_str!("../test_input/day24.example1.txt")).unwrap(), (7, 27)));
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(17906, part1(&parse(include_str!("../input/2023/day24.txt"))));
+        assert_eq!(17906, part1(&parse(include_str!("../input/2023/day24.txt")).unwrap()));
     }
 
     #[test]
     fn part2_example1() {
-        assert_eq!(47, part2(&parse(include_str!("../test_input/day24.example1.txt"))));
+        assert_eq!(47, part2(&parse(include_str!("../test_input/day24.example1.txt")).unwrap()));
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(571093786416929, part2(&parse(include_str!("../input/2023/day24.txt"))));
+        assert_eq!(571093786416929, part2(&parse(include_str!("../input/2023/day24.txt")).unwrap()));
     }
 }
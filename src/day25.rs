@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
 use aoc_runner_derive::{aoc, aoc_generator};
+use anyhow::Result;
 
 type Input = Vec<(String, String)>;
 
@@ -41,23 +43,42 @@ fn two_component_lens<'a>(edges: impl IntoIterator<Item = &'a (String, String)>)
     (seen.len(), connections.keys().len() - seen.len())
 }
 
-#[aoc(day25, part1)]
-fn part1(input: &Input) -> usize {
-    let edges_to_be_removed = [
-        ("sxx", "zvk"),
-        ("njx", "pbx"),
-        ("pzr", "sss"),
-    ];
+const EDGES_TO_BE_REMOVED: [(&str, &str); 3] = [
+    ("sxx", "zvk"),
+    ("njx", "pbx"),
+    ("pzr", "sss"),
+];
 
+fn split(input: &Input) -> (usize, usize) {
     let pruned_edges = input
         .iter()
-        .filter(|(a, b)| !(edges_to_be_removed.contains(&(a, b)) || edges_to_be_removed.contains(&(b, a))));
+        .filter(|(a, b)| !(EDGES_TO_BE_REMOVED.contains(&(a, b)) || EDGES_TO_BE_REMOVED.contains(&(b, a))));
+
+    two_component_lens(pruned_edges)
+}
 
-    let (a, b) = two_component_lens(pruned_edges);
+#[aoc(day25, part1)]
+fn part1(input: &Input) -> usize {
+    let (a, b) = split(input);
 
     a * b
 }
 
+pub(crate) fn explain(input: &str) -> Result<String> {
+    let input = parse(input);
+    let mut explanation = String::new();
+
+    for (a, b) in EDGES_TO_BE_REMOVED {
+        let present = input.iter().any(|(c, d)| [(a, b), (b, a)].contains(&(c.as_str(), d.as_str())));
+        writeln!(explanation, "Removed {a}/{b}{}", if present { "" } else { " (not in input)" })?;
+    }
+
+    let (a, b) = split(&input);
+    writeln!(explanation, "Components: {a} and {b}")?;
+
+    Ok(explanation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Example {
+    pub part: u32,
+    pub input: String,
+    /// Emphasized values (`<code><em>…</em></code>`) between this example and the next one, in page order.
+    pub candidates: Vec<String>,
+}
+
+impl Example {
+    /// The last emphasized value, since puzzle pages state the answer after the values leading up to it.
+    pub fn answer(&self) -> Option<&str> {
+        self.candidates.last().map(String::as_str)
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn strip_tags(s: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_tag = false;
+
+    for c in s.chars() {
+        match c {
+            '<' => { in_tag = true; },
+            '>' if in_tag => { in_tag = false; },
+            _ if !in_tag => { stripped.push(c); },
+            _ => {},
+        }
+    }
+
+    decode_entities(&stripped)
+}
+
+fn emphasized_values(html: &str) -> Vec<String> {
+    let markers = [("<code><em>", "</em></code>"), ("<em><code>", "</code></em>")];
+    let mut values = vec![];
+    let mut rest = html;
+
+    while let Some((start, (open, close))) = markers
+        .iter()
+        .filter_map(|&(open, close)| rest.find(open).map(|start| (start, (open, close))))
+        .min_by_key(|&(start, _)| start)
+    {
+        let inner = &rest[(start + open.len())..];
+        let Some(end) = inner.find(close) else { break };
+        let value = strip_tags(&inner[..end]);
+
+        if !value.is_empty() && !value.contains(char::is_whitespace) {
+            values.push(value);
+        }
+
+        rest = &inner[(end + close.len())..];
+    }
+
+    values
+}
+
+/// Finds the `<pre><code>` blocks of each part's `<article>`, together with the answers emphasized after them. A part
+/// without an example of its own reuses the last example of the previous part.
+pub fn extract_examples(html: &str) -> Vec<Example> {
+    let mut examples: Vec<Example> = vec![];
+
+    for (part, article) in html.split("<article").skip(1).enumerate() {
+        let part = part as u32 + 1;
+        let article = article.split_once("</article>").map_or(article, |(article, _)| article);
+        let mut blocks = article.split("<pre><code>");
+        let preamble = blocks.next().unwrap_or_default();
+        let mut part_examples = vec![];
+
+        for block in blocks {
+            let Some((input, after)) = block.split_once("</code></pre>") else { continue };
+
+            part_examples.push(Example {
+                part,
+                input: strip_tags(input),
+                candidates: emphasized_values(after),
+            });
+        }
+
+        if part_examples.is_empty() {
+            if let Some(previous) = examples.last() {
+                part_examples.push(Example {
+                    part,
+                    input: previous.input.clone(),
+                    candidates: emphasized_values(preamble),
+                });
+            }
+        }
+
+        examples.extend(part_examples);
+    }
+
+    examples
+}
+
+pub fn file_name(day: u32, part: u32, answer: &str) -> String {
+    format!("day{day:02}.part{part}.{answer}.txt")
+}
+
+/// Writes every example that has an answer into `dir`, asking `prompt` for an answer whenever none is emphasized.
+/// Examples that already have a file are left alone.
+pub fn write_examples(
+    dir: &Path,
+    day: u32,
+    examples: &[Example],
+    mut prompt: impl FnMut(&Example) -> Result<Option<String>>,
+) -> Result<Vec<PathBuf>> {
+    let mut written = vec![];
+
+    for example in examples {
+        let answer = match example.answer() {
+            Some(answer) => answer.to_string(),
+            None => match prompt(example)? {
+                Some(answer) => answer,
+                None => continue,
+            },
+        };
+
+        let path = dir.join(file_name(day, example.part, &answer));
+
+        if !path.exists() {
+            fs::write(&path, &example.input)?;
+            written.push(path);
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use super::*;
+
+    const PAGE: &str = r#"<main>
+<article class="day-desc"><h2>--- Day 8: Haunted Wasteland ---</h2>
+<p>For example:</p>
+<pre><code>RL
+
+AAA = (BBB, CCC)
+</code></pre>
+<p>Starting with <code>AAA</code>, you need to look up the next element, reaching <code>ZZZ</code> in <code><em>2</em></code> steps.</p>
+<pre><code>LLR
+
+AAA = (BBB, BBB)
+</code></pre>
+<p>Here, <code><em>6</em></code> steps.</p>
+</article>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>After <em>one</em> step, you reach <code><em>3</em></code> and then <em><code>4</code></em> nodes ending in <code>Z</code>, &lt;done&gt;.</p>
+</article>
+</main>"#;
+
+    #[test]
+    fn extract() {
+        let examples = extract_examples(PAGE);
+
+        assert_eq!(3, examples.len());
+        assert_eq!((1, "RL\n\nAAA = (BBB, CCC)\n", Some("2")), (examples[0].part, examples[0].input.as_str(), examples[0].answer()));
+        assert_eq!((1, Some("6")), (examples[1].part, examples[1].answer()));
+        assert_eq!((2, examples[1].input.as_str()), (examples[2].part, examples[2].input.as_str()));
+        assert_eq!(vec!["3", "4"], examples[2].candidates);
+        assert_eq!(Some("4"), examples[2].answer());
+
+        let calibration = "<article><pre><code>1abc2\ntreb7uchet\n</code></pre><p>Values <code><em>12</em></code> and \
+                           <code><em>77</em></code> add up to <code><em>89</em></code>.</p></article>";
+        assert_eq!(Some("89"), extract_examples(calibration)[0].answer());
+    }
+
+    #[test]
+    fn write_prompts_only_without_answer() {
+        let dir = std::env::temp_dir().join(format!("aoc-2023-examples-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut examples = extract_examples(PAGE);
+        examples[2].candidates.clear();
+
+        let mut prompted = vec![];
+        let written = write_examples(&dir, 8, &examples, |example| {
+            prompted.push(example.part);
+            Ok(Some("4".to_string()))
+        }).unwrap();
+
+        assert_eq!(vec![2], prompted);
+        assert_eq!(
+            vec!["day08.part1.2.txt", "day08.part1.6.txt", "day08.part2.4.txt"],
+            written.iter().map(|path| path.file_name().unwrap().to_str().unwrap()).collect_vec(),
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
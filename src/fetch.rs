@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context, Result};
+use crate::client::Client;
+
+pub const YEAR: u32 = 2023;
+
+// Puzzles unlock at midnight EST (UTC-5); this is 2023-12-01T05:00:00Z.
+const FIRST_UNLOCK_SECS: u64 = 1701406800;
+
+pub fn unlock_time(day: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(FIRST_UNLOCK_SECS + (day as u64 - 1) * 24 * 60 * 60)
+}
+
+pub fn is_unlocked(day: u32, now: SystemTime) -> bool {
+    now >= unlock_time(day)
+}
+
+/// Where `cargo aoc` expects the input for `day`.
+pub fn input_path(input_dir: &Path, day: u32) -> PathBuf {
+    input_dir.join(YEAR.to_string()).join(format!("day{day}.txt"))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Fetched {
+    Cached(PathBuf),
+    Downloaded(PathBuf),
+}
+
+pub fn fetch_input(client: &Client, input_dir: &Path, day: u32, now: SystemTime) -> Result<Fetched> {
+    if !(1..=25).contains(&day) {
+        bail!("Invalid day: {day}");
+    }
+
+    let path = input_path(input_dir, day);
+
+    if path.exists() {
+        return Ok(Fetched::Cached(path));
+    }
+
+    if !is_unlocked(day, now) {
+        bail!("Day {day} is not unlocked yet");
+    }
+
+    let input = client.get(&format!("/{YEAR}/day/{day}/input"))?;
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, input).context(format!("Could not write {}", path.display()))?;
+
+    Ok(Fetched::Downloaded(path))
+}
+
+/// Fetches every unlocked day that has no cached input yet.
+pub fn fetch_missing_inputs(client: &Client, input_dir: &Path, now: SystemTime) -> Result<Vec<Fetched>> {
+    (1..=25)
+        .filter(|&day| is_unlocked(day, now))
+        .map(|day| fetch_input(client, input_dir, day, now))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::stub;
+
+    fn temp_input_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-2023-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn unlock_times() {
+        assert!(!is_unlocked(1, UNIX_EPOCH + Duration::from_secs(1701406799)));
+        assert!(is_unlocked(1, UNIX_EPOCH + Duration::from_secs(1701406800)));
+        assert!(!is_unlocked(25, unlock_time(24) + Duration::from_secs(24 * 60 * 60 - 1)));
+    }
+
+    #[test]
+    fn fetch_and_cache() {
+        let (base_url, requests) = stub::serve(vec![(200, "1abc2\n")]);
+        let client = Client::new(&base_url, "secret").with_min_request_interval(Duration::ZERO);
+        let input_dir = temp_input_dir("fetch");
+        let path = input_path(&input_dir, 1);
+
+        assert_eq!(Fetched::Downloaded(path.clone()), fetch_input(&client, &input_dir, 1, unlock_time(1)).unwrap());
+        assert_eq!("1abc2\n", fs::read_to_string(&path).unwrap());
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /2023/day/1/input "));
+        assert!(request.contains("session=secret"));
+        assert!(request.to_ascii_lowercase().contains(&format!("user-agent: {}", "github.com/auno/adventofcode-2023")));
+
+        assert_eq!(Fetched::Cached(path), fetch_input(&client, &input_dir, 1, unlock_time(1)).unwrap());
+        fs::remove_dir_all(input_dir).unwrap();
+    }
+
+    #[test]
+    fn refuse_locked_day() {
+        let client = Client::new("http://127.0.0.1:9", "secret");
+        let input_dir = temp_input_dir("locked");
+
+        assert!(fetch_input(&client, &input_dir, 2, unlock_time(1)).is_err());
+        assert!(!input_path(&input_dir, 2).exists());
+    }
+}
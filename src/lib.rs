@@ -1,15 +1,21 @@
+use anyhow::Result;
 use aoc_runner_derive::aoc_lib;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
+pub mod client;
+pub mod examples;
+pub mod fetch;
+pub mod submit;
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
 mod day11;
 mod day12;
 mod day13;
@@ -25,5 +31,50 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+mod parser;
+
+/// The intermediate reasoning behind a day's answers, for the days that can explain themselves.
+pub fn explain(day: u32, input: &str) -> Result<Option<String>> {
+    let input = input.trim_end_matches('\n');
+
+    Ok(Some(
+        match day {
+            1 => day01::explain(input)?,
+            4 => day04::explain(input)?,
+            7 => day07::explain(input)?,
+            8 => day08::explain(input)?,
+            13 => day13::explain(input)?,
+            14 => day14::explain(input)?,
+            19 => day19::explain(input)?,
+            20 => day20::explain(input)?,
+            25 => day25::explain(input)?,
+            _ => return Ok(None),
+        }
+    ))
+}
+
+/// How [`render`] draws a day's input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Text with ANSI colors, for the terminal.
+    Ansi,
+    /// A standalone HTML page.
+    Html,
+    /// A standalone SVG image.
+    Svg,
+}
+
+/// A highlighted drawing of the input, for the days that can draw themselves. Not every day supports every format.
+pub fn render(day: u32, input: &str, format: Format) -> Result<Option<String>> {
+    let input = input.trim_end_matches('\n');
+
+    Ok(Some(
+        match day {
+            3 => day03::render(input, format)?,
+            10 => day10::render(input, format)?,
+            _ => return Ok(None),
+        }
+    ))
+}
 
 aoc_lib! { year = 2023 }
@@ -0,0 +1,350 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::fs;
+use std::io::{stdin, stdout, Write};
+use anyhow::{anyhow, bail, Context, Result};
+use aoc_runner::ArcStr;
+use clap::{Args, Parser, Subcommand};
+use aoc_2023::*;
+use aoc_2023::client::Client;
+use aoc_2023::day01::{self, Vocabulary};
+use aoc_2023::day02::{self, Cubes};
+use aoc_2023::day04::{self, Copies, Points, ScoringRule};
+use aoc_2023::day05;
+use aoc_2023::day10::{self, AreaMethod};
+use aoc_2023::examples::{extract_examples, write_examples, Example};
+use aoc_2023::fetch::{fetch_input, fetch_missing_inputs, input_path, Fetched};
+use aoc_2023::submit::{submit, Log, Verdict};
+
+#[derive(Parser)]
+#[command(about = "Advent of Code 2023 helpers")]
+struct Cli {
+    /// Base URL of the Advent of Code website [default: $AOC_BASE_URL or https://adventofcode.com]
+    #[arg(long, global = true)]
+    base_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct DayOptions {
+    /// Day 1: spelled-out digits, as a built-in language (en, de, fr, sv) or a file of `token = digit` lines
+    #[arg(long)]
+    vocabulary: Option<String>,
+
+    /// Day 1: also match the built-in language's word for zero
+    #[arg(long)]
+    zero: bool,
+
+    /// Day 1: match spelled-out digits case-insensitively
+    #[arg(long)]
+    case_insensitive: bool,
+
+    /// Day 2: the bag to check games against, e.g. `red=12,green=13,blue=14,yellow=5`
+    #[arg(long)]
+    bag: Option<Cubes>,
+
+    /// Day 4: points scheme (exponential, linear or fibonacci) [default: exponential]
+    #[arg(long)]
+    points: Option<Points>,
+
+    /// Day 4: how many following cards each winning number wins copies of [default: 1]
+    #[arg(long)]
+    copy_reach: Option<usize>,
+
+    /// Day 4: how many copies of each following card are won [default: 1]
+    #[arg(long)]
+    copy_multiplier: Option<u64>,
+
+    /// Day 5: the category the seeds line holds values of [default: seed]
+    #[arg(long)]
+    from: Option<String>,
+
+    /// Day 5: the category to find the lowest value in [default: location]
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Day 10: how to count the enclosed tiles (flood-fill, shoelace or cross-check) [default: shoelace]
+    #[arg(long)]
+    area: Option<AreaMethod>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compute answers from the cached input
+    Run {
+        #[arg(long)]
+        day: u32,
+
+        /// Only run this part [default: both]
+        #[arg(long)]
+        part: Option<u32>,
+
+        #[arg(long, default_value = "input")]
+        input_dir: PathBuf,
+
+        /// Also print the intermediate reasoning, for days that support it
+        #[arg(long)]
+        explain: bool,
+
+        #[command(flatten)]
+        options: DayOptions,
+    },
+    /// Download puzzle inputs that are not cached yet
+    Fetch {
+        /// Only fetch this day [default: every unlocked day]
+        #[arg(long)]
+        day: Option<u32>,
+
+        #[arg(long, default_value = "input")]
+        input_dir: PathBuf,
+    },
+    /// Compute an answer and submit it
+    Submit {
+        #[arg(long)]
+        day: u32,
+
+        #[arg(long)]
+        part: u32,
+
+        #[arg(long, default_value = "input")]
+        input_dir: PathBuf,
+
+        /// Where every submitted answer and its verdict is recorded
+        #[arg(long, default_value = "input/2023/submissions.log")]
+        log: PathBuf,
+    },
+    /// Draw the cached input with highlighting, for days that support it
+    Render {
+        #[arg(long)]
+        day: u32,
+
+        #[arg(long, default_value = "input")]
+        input_dir: PathBuf,
+
+        /// Write an HTML page to this file instead of printing ANSI colored text
+        #[arg(long)]
+        html: Option<PathBuf>,
+
+        /// Write an SVG image to this file instead of printing ANSI colored text
+        #[arg(long, conflicts_with = "html")]
+        svg: Option<PathBuf>,
+    },
+    /// Extract examples and their answers from a saved puzzle page into test input files
+    Examples {
+        #[arg(long)]
+        day: u32,
+
+        /// The puzzle page, saved as HTML
+        page: PathBuf,
+
+        #[arg(long, default_value = "test_input")]
+        test_input_dir: PathBuf,
+    },
+}
+
+fn solve(day: u32, part: u32, input: &str) -> Result<String> {
+    let input = ArcStr::from(input);
+    let runner = match (day, part) {
+        (1, 1) => Factory::day1_part1(input),
+        (1, 2) => Factory::day1_part2(input),
+        (2, 1) => Factory::day2_part1(input),
+        (2, 2) => Factory::day2_part2(input),
+        (3, 1) => Factory::day3_part1(input),
+        (3, 2) => Factory::day3_part2(input),
+        (4, 1) => Factory::day4_part1(input),
+        (4, 2) => Factory::day4_part2(input),
+        (5, 1) => Factory::day5_part1(input),
+        (5, 2) => Factory::day5_part2(input),
+        (6, 1) => Factory::day6_part1(input),
+        (6, 2) => Factory::day6_part2(input),
+        (7, 1) => Factory::day7_part1(input),
+        (7, 2) => Factory::day7_part2(input),
+        (8, 1) => Factory::day8_part1(input),
+        (8, 2) => Factory::day8_part2(input),
+        (9, 1) => Factory::day9_part1(input),
+        (9, 2) => Factory::day9_part2(input),
+        (10, 1) => Factory::day10_part1(input),
+        (10, 2) => Factory::day10_part2(input),
+        (11, 1) => Factory::day11_part1(input),
+        (11, 2) => Factory::day11_part2(input),
+        (12, 1) => Factory::day12_part1(input),
+        (12, 2) => Factory::day12_part2(input),
+        (13, 1) => Factory::day13_part1(input),
+        (13, 2) => Factory::day13_part2(input),
+        (14, 1) => Factory::day14_part1(input),
+        (14, 2) => Factory::day14_part2(input),
+        (15, 1) => Factory::day15_part1(input),
+        (15, 2) => Factory::day15_part2(input),
+        (16, 1) => Factory::day16_part1(input),
+        (16, 2) => Factory::day16_part2(input),
+        (17, 1) => Factory::day17_part1(input),
+        (17, 2) => Factory::day17_part2(input),
+        (18, 1) => Factory::day18_part1(input),
+        (18, 2) => Factory::day18_part2(input),
+        (19, 1) => Factory::day19_part1(input),
+        (19, 2) => Factory::day19_part2(input),
+        (20, 1) => Factory::day20_part1(input),
+        (20, 2) => Factory::day20_part2(input),
+        (21, 1) => Factory::day21_part1(input),
+        (21, 2) => Factory::day21_part2(input),
+        (22, 1) => Factory::day22_part1(input),
+        (22, 2) => Factory::day22_part2(input),
+        (23, 1) => Factory::day23_part1(input),
+        (23, 2) => Factory::day23_part2(input),
+        (24, 1) => Factory::day24_part1(input),
+        (24, 2) => Factory::day24_part2(input),
+        (25, 1) => Factory::day25_part1(input),
+        _ => bail!("No solution for day {day} part {part}"),
+    }.map_err(|e| anyhow!("Generator failed: {e}"))?;
+
+    Ok(runner.try_run().map_err(|e| anyhow!("Runner failed: {e}"))?.to_string())
+}
+
+fn solve_with_options(day: u32, part: u32, input: &str, options: &DayOptions) -> Result<String> {
+    match (day, part) {
+        (1, 2) if options.vocabulary.is_some() || options.zero || options.case_insensitive => {
+            let vocabulary = match options.vocabulary.as_deref().unwrap_or("en") {
+                path if Path::new(path).is_file() => Vocabulary::load(Path::new(path), options.case_insensitive)?,
+                language => Vocabulary::builtin(language, options.zero, options.case_insensitive)?,
+            };
+
+            Ok(day01::calibrate(input, &vocabulary)?.to_string())
+        },
+        (2, 1) => match &options.bag {
+            Some(bag) => Ok(day02::feasible_game_ids(&day02::parse(input)?, bag).to_string()),
+            None => solve(day, part, input),
+        },
+        (4, _) if options.points.is_some() || options.copy_reach.is_some() || options.copy_multiplier.is_some() => {
+            let default = Copies::default();
+            let rule = ScoringRule {
+                points: options.points.unwrap_or_default(),
+                copies: Copies {
+                    reach: options.copy_reach.unwrap_or(default.reach),
+                    multiplier: options.copy_multiplier.unwrap_or(default.multiplier),
+                },
+            };
+            let cards = day04::parse(input)?;
+
+            match part {
+                1 => Ok(rule.total_points(&cards)?.to_string()),
+                _ => Ok(rule.total_cards(&cards).to_string()),
+            }
+        },
+        (5, _) if options.from.is_some() || options.to.is_some() => {
+            let almanac = day05::parse(input)?;
+            let from = options.from.as_deref().unwrap_or("seed");
+            let to = options.to.as_deref().unwrap_or("location");
+
+            Ok(almanac.lowest(from, to, &almanac.seed_intervals(part == 2))?.to_string())
+        },
+        (10, 2) => match options.area {
+            Some(method) => Ok(day10::enclosed_tiles(&day10::parse(input)?, method)?.to_string()),
+            None => solve(day, part, input),
+        },
+        _ => solve(day, part, input),
+    }
+}
+
+fn read_input(input_dir: &Path, day: u32) -> Result<String> {
+    let path = input_path(input_dir, day);
+    fs::read_to_string(&path).context(format!("Could not read {}; try the fetch command", path.display()))
+}
+
+fn prompt_answer(example: &Example) -> Result<Option<String>> {
+    println!("Part {} example:\n{}", example.part, example.input);
+    println!("Emphasized values: {}", example.candidates.join(", "));
+    print!("Answer (empty to skip): ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Run { day, part, input_dir, explain, options } => {
+            let input = read_input(&input_dir, day)?;
+            let parts = match part {
+                Some(part) => vec![part],
+                None if day == 25 => vec![1],
+                None => vec![1, 2],
+            };
+
+            for part in parts {
+                println!("Day {day} part {part}: {}", solve_with_options(day, part, &input, &options)?);
+            }
+
+            if explain {
+                match aoc_2023::explain(day, &input)? {
+                    Some(explanation) => print!("{explanation}"),
+                    None => println!("No explanation available for day {day}"),
+                }
+            }
+        },
+        Command::Fetch { day, input_dir } => {
+            let client = Client::from_environment(cli.base_url.as_deref())?;
+            let now = SystemTime::now();
+            let fetched = match day {
+                Some(day) => vec![fetch_input(&client, &input_dir, day, now)?],
+                None => fetch_missing_inputs(&client, &input_dir, now)?,
+            };
+
+            for f in fetched {
+                match f {
+                    Fetched::Cached(path) => println!("Cached: {}", path.display()),
+                    Fetched::Downloaded(path) => println!("Downloaded: {}", path.display()),
+                }
+            }
+        },
+        Command::Submit { day, part, input_dir, log } => {
+            let input = read_input(&input_dir, day)?;
+            let answer = solve(day, part, &input)?;
+            let client = Client::from_environment(cli.base_url.as_deref())?;
+            let mut log = Log::load(&log)?;
+
+            println!("Day {day} part {part}: {answer}");
+
+            match submit(&client, &mut log, day, part, &answer)? {
+                Verdict::Correct => println!("Correct!"),
+                Verdict::TooHigh => println!("Wrong: too high"),
+                Verdict::TooLow => println!("Wrong: too low"),
+                Verdict::Incorrect => println!("Wrong"),
+                Verdict::RateLimited(wait) => println!("Rate limited; wait {}s before trying again", wait.as_secs()),
+                Verdict::AlreadySolved => println!("Already solved"),
+            }
+        },
+        Command::Render { day, input_dir, html, svg } => {
+            let input = read_input(&input_dir, day)?;
+            let (format, path) = match (html, svg) {
+                (Some(path), _) => (Format::Html, Some(path)),
+                (None, Some(path)) => (Format::Svg, Some(path)),
+                (None, None) => (Format::Ansi, None),
+            };
+
+            match (aoc_2023::render(day, &input, format)?, path) {
+                (None, _) => println!("No rendering available for day {day}"),
+                (Some(rendering), None) => print!("{rendering}"),
+                (Some(rendering), Some(path)) => {
+                    fs::write(&path, rendering).context(format!("Could not write {}", path.display()))?;
+                    println!("Wrote {}", path.display());
+                },
+            }
+        },
+        Command::Examples { day, page, test_input_dir } => {
+            let html = fs::read_to_string(&page).context(format!("Could not read {}", page.display()))?;
+
+            for path in write_examples(&test_input_dir, day, &extract_examples(&html), prompt_answer)? {
+                println!("Wrote {}", path.display());
+            }
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::{Error, Result};
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A slice of the puzzle input that remembers where in the input it starts (1-based line and column).
+#[derive(Copy, Clone, Debug)]
+pub struct Span<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Display for Span<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.text)
+    }
+}
+
+impl<'a> Span<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Span { text, line: 1, column: 1 }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn error(&self, message: impl Display) -> Error {
+        ParseError { line: self.line, column: self.column, message: message.to_string() }.into()
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Span<'a> {
+        let prefix = &self.text[..start];
+        let (line, column) = match prefix.rfind('\n') {
+            Some(pos) => (self.line + prefix.matches('\n').count(), prefix[(pos + 1)..].chars().count() + 1),
+            None => (self.line, self.column + prefix.chars().count()),
+        };
+
+        Span { text: &self.text[start..end], line, column }
+    }
+
+    fn subspan(&self, sub: &'a str) -> Span<'a> {
+        let start = sub.as_ptr() as usize - self.text.as_ptr() as usize;
+        self.slice(start, start + sub.len())
+    }
+
+    pub fn trim(&self) -> Span<'a> {
+        self.subspan(self.text.trim())
+    }
+
+    pub fn parse<T>(&self) -> Result<T> where T: FromStr, T::Err: Display {
+        self.text
+            .parse()
+            .map_err(|e| self.error(format!("Could not parse `{}`: {e}", self.text)))
+    }
+
+    pub fn strip_prefix(&self, prefix: &str) -> Result<Span<'a>> {
+        match self.text.strip_prefix(prefix) {
+            Some(rest) => Ok(self.subspan(rest)),
+            None => Err(self.error(format!("Expected `{prefix}`: {}", self.text))),
+        }
+    }
+
+    pub fn strip_suffix(&self, suffix: &str) -> Result<Span<'a>> {
+        match self.text.strip_suffix(suffix) {
+            Some(rest) => Ok(self.subspan(rest)),
+            None => Err(self.error(format!("Expected trailing `{suffix}`: {}", self.text))),
+        }
+    }
+
+    pub fn split_once(&self, separator: &str) -> Result<(Span<'a>, Span<'a>)> {
+        let pos = self.text
+            .find(separator)
+            .ok_or_else(|| self.error(format!("Expected `{separator}`: {}", self.text)))?;
+
+        Ok((self.slice(0, pos), self.slice(pos + separator.len(), self.text.len())))
+    }
+
+    pub fn split(self, separator: &'a str) -> impl Iterator<Item = Span<'a>> + 'a {
+        let mut rest = self;
+
+        self.text.split(separator).map(move |piece| {
+            let span = rest.slice(0, piece.len());
+            rest = rest.slice((piece.len() + separator.len()).min(rest.text.len()), rest.text.len());
+            span
+        })
+    }
+
+    pub fn lines(self) -> impl Iterator<Item = Span<'a>> + 'a {
+        self.text.lines().enumerate().map(move |(j, line)| Span {
+            text: line,
+            line: self.line + j,
+            column: if j == 0 { self.column } else { 1 },
+        })
+    }
+
+    /// Splits on blank lines.
+    pub fn sections(self) -> impl Iterator<Item = Span<'a>> + 'a {
+        self.split("\n\n")
+    }
+
+    pub fn chars(self) -> impl Iterator<Item = (char, Span<'a>)> + 'a {
+        self.text
+            .char_indices()
+            .map(move |(i, c)| (c, self.subspan(&self.text[i..(i + c.len_utf8())])))
+    }
+}
+
+/// Numbers separated by whitespace and/or commas, e.g. `79 14 55 13` or `19, 13, 30`.
+pub fn numbers<T>(span: Span) -> Result<Vec<T>> where T: FromStr, T::Err: Display {
+    span.text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| span.subspan(s).parse())
+        .collect()
+}
+
+/// Splits `key: value` into its trimmed key and value.
+pub fn key_value(span: Span) -> Result<(Span, Span)> {
+    let (key, value) = span.split_once(":")?;
+    Ok((key.trim(), value.trim()))
+}
+
+/// The value of a `key: value` line with a known key.
+pub fn header<'a>(span: Span<'a>, key: &str) -> Result<Span<'a>> {
+    let (actual_key, value) = key_value(span)?;
+
+    if actual_key.as_str() != key {
+        return Err(actual_key.error(format!("Expected `{key}`, found `{actual_key}`")));
+    }
+
+    Ok(value)
+}
+
+/// A labelled tuple such as `AAA = (BBB, CCC)`.
+pub fn labelled_tuple(span: Span) -> Result<(Span, Vec<Span>)> {
+    let (label, tuple) = span.split_once(" = ")?;
+    let elements = tuple
+        .strip_prefix("(")?
+        .strip_suffix(")")?
+        .split(",")
+        .map(|element| element.trim())
+        .collect();
+
+    Ok((label, elements))
+}
+
+pub type Grid<T> = (usize, usize, HashMap<(usize, usize), T>);
+
+/// A rectangular char grid keyed by `(row, column)`. Cells for which `tile` returns `None` are left out.
+pub fn grid<T>(span: Span, tile: impl Fn(char) -> Result<Option<T>>) -> Result<Grid<T>> {
+    let width = span.lines().next().ok_or_else(|| span.error("Unexpected empty grid"))?.text.chars().count();
+    let mut height = 0;
+    let mut map = HashMap::new();
+
+    for (j, line) in span.lines().enumerate() {
+        if line.text.chars().count() != width {
+            return Err(line.error(format!("Expected {width} columns, found {}", line.text.chars().count())));
+        }
+
+        for (i, (c, cell)) in line.chars().enumerate() {
+            if let Some(t) = tile(c).map_err(|e| cell.error(e))? {
+                map.insert((j, i), t);
+            }
+        }
+
+        height += 1;
+    }
+
+    Ok((height, width, map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(error: Error) -> (usize, usize) {
+        let error = error.downcast::<ParseError>().unwrap();
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn numbers_positioned_error() {
+        let input = Span::new("seeds: 79 14\nsoil: 55 x3");
+        let mut lines = input.lines();
+
+        assert_eq!(vec![79, 14], numbers::<u32>(header(lines.next().unwrap(), "seeds").unwrap()).unwrap());
+        assert_eq!((2, 10), position(numbers::<u32>(header(lines.next().unwrap(), "soil").unwrap()).unwrap_err()));
+    }
+
+    #[test]
+    fn sections_track_lines() {
+        let input = Span::new("a\nb\n\nc\n\n\nd");
+        let sections = input.sections().map(|s| (s.as_str(), s.line)).collect::<Vec<_>>();
+
+        assert_eq!(vec![("a\nb", 1), ("c", 4), ("\nd", 6)], sections);
+    }
+
+    #[test]
+    fn labelled_tuple_elements() {
+        let (label, elements) = labelled_tuple(Span::new("AAA = (BBB, CCC)")).unwrap();
+
+        assert_eq!("AAA", label.as_str());
+        assert_eq!(vec!["BBB", "CCC"], elements.iter().map(Span::as_str).collect::<Vec<_>>());
+        assert_eq!(13, elements[1].column);
+        assert_eq!((1, 7), position(labelled_tuple(Span::new("AAA = BBB, CCC)")).unwrap_err()));
+    }
+
+    #[test]
+    fn grid_rejects_unknown_tile() {
+        let tile = |c| match c {
+            '.' => Ok(None),
+            '#' => Ok(Some(())),
+            _ => anyhow::bail!("Invalid tile: {c}"),
+        };
+
+        let (height, width, map) = grid(Span::new("#.\n.#\n"), tile).unwrap();
+        assert_eq!((2, 2, 2), (height, width, map.len()));
+        assert_eq!((2, 2), position(grid(Span::new("#.\n.x"), tile).unwrap_err()));
+        assert_eq!((2, 1), position(grid(Span::new("#.\n.#."), tile).unwrap_err()));
+    }
+}
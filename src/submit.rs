@@ -0,0 +1,280 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context, Error, Result};
+use crate::client::Client;
+use crate::fetch::YEAR;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    Incorrect,
+    RateLimited(Duration),
+    AlreadySolved,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Correct => f.write_str("correct"),
+            Verdict::TooHigh => f.write_str("too-high"),
+            Verdict::TooLow => f.write_str("too-low"),
+            Verdict::Incorrect => f.write_str("incorrect"),
+            Verdict::RateLimited(wait) => write!(f, "rate-limited:{}", wait.as_secs()),
+            Verdict::AlreadySolved => f.write_str("already-solved"),
+        }
+    }
+}
+
+impl FromStr for Verdict {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(
+            match s {
+                "correct" => Verdict::Correct,
+                "too-high" => Verdict::TooHigh,
+                "too-low" => Verdict::TooLow,
+                "incorrect" => Verdict::Incorrect,
+                "already-solved" => Verdict::AlreadySolved,
+                _ => match s.strip_prefix("rate-limited:") {
+                    Some(secs) => Verdict::RateLimited(Duration::from_secs(secs.parse()?)),
+                    None => bail!("Invalid verdict: {s}"),
+                }
+            }
+        )
+    }
+}
+
+impl Verdict {
+    fn is_wrong(&self) -> bool {
+        matches!(self, Verdict::TooHigh | Verdict::TooLow | Verdict::Incorrect)
+    }
+}
+
+/// Parses a wait like `1m 30s` as found in "You have 1m 30s left to wait".
+fn parse_wait(s: &str) -> Result<Duration> {
+    s.split_whitespace()
+        .map(|token| {
+            let (i, _) = token.char_indices().last().context(format!("Invalid wait: {s}"))?;
+            let (value, unit) = token.split_at(i);
+            let value: u64 = value.parse().context(format!("Invalid wait: {s}"))?;
+
+            Ok(
+                match unit {
+                    "h" => value * 60 * 60,
+                    "m" => value * 60,
+                    "s" => value,
+                    _ => bail!("Invalid wait: {s}"),
+                }
+            )
+        })
+        .sum::<Result<u64>>()
+        .map(Duration::from_secs)
+}
+
+pub fn parse_verdict(response: &str) -> Result<Verdict> {
+    if response.contains("That's the right answer") {
+        return Ok(Verdict::Correct);
+    }
+
+    if response.contains("your answer is too high") {
+        return Ok(Verdict::TooHigh);
+    }
+
+    if response.contains("your answer is too low") {
+        return Ok(Verdict::TooLow);
+    }
+
+    if response.contains("That's not the right answer") {
+        return Ok(Verdict::Incorrect);
+    }
+
+    if response.contains("You gave an answer too recently") {
+        let wait = response
+            .split_once("You have ")
+            .and_then(|(_, rest)| rest.split_once(" left to wait"))
+            .map(|(wait, _)| parse_wait(wait))
+            .transpose()?
+            .unwrap_or_default();
+
+        return Ok(Verdict::RateLimited(wait));
+    }
+
+    if response.contains("Did you already complete it?") {
+        return Ok(Verdict::AlreadySolved);
+    }
+
+    bail!("Unrecognized response: {}", response.trim())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Guess {
+    pub timestamp: u64,
+    pub day: u32,
+    pub part: u32,
+    pub answer: String,
+    pub verdict: Verdict,
+}
+
+impl Display for Guess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}\t{}\t{}", self.timestamp, self.day, self.part, self.answer, self.verdict)
+    }
+}
+
+impl FromStr for Guess {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split('\t').collect::<Vec<_>>()[..] {
+            [timestamp, day, part, answer, verdict] => Ok(Guess {
+                timestamp: timestamp.parse()?,
+                day: day.parse()?,
+                part: part.parse()?,
+                answer: answer.to_string(),
+                verdict: verdict.parse()?,
+            }),
+            _ => bail!("Invalid guess: {s}"),
+        }
+    }
+}
+
+/// Every submitted answer, one tab-separated [`Guess`] per line.
+pub struct Log {
+    path: PathBuf,
+    guesses: Vec<Guess>,
+}
+
+impl Log {
+    pub fn load(path: &Path) -> Result<Self> {
+        let guesses = match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().map(str::parse).collect::<Result<_>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e).context(format!("Could not read {}", path.display())),
+        };
+
+        Ok(Log { path: path.to_path_buf(), guesses })
+    }
+
+    pub fn guesses(&self, day: u32, part: u32) -> impl Iterator<Item = &Guess> {
+        self.guesses.iter().filter(move |guess| guess.day == day && guess.part == part)
+    }
+
+    /// Returns the known verdict if this answer was already accepted, and fails if submitting it would be pointless.
+    pub fn check(&self, day: u32, part: u32, answer: &str) -> Result<Option<Verdict>> {
+        let numeric_answer = answer.parse::<i128>().ok();
+
+        for guess in self.guesses(day, part) {
+            if guess.verdict == Verdict::Correct {
+                if guess.answer == answer {
+                    return Ok(Some(Verdict::Correct));
+                }
+
+                bail!("Day {day} part {part} was already solved with {}", guess.answer);
+            }
+
+            if guess.answer == answer && guess.verdict.is_wrong() {
+                bail!("{answer} was already rejected as {}", guess.verdict);
+            }
+
+            match (numeric_answer, guess.answer.parse::<i128>(), guess.verdict) {
+                (Some(a), Ok(g), Verdict::TooHigh) if a >= g => bail!("{answer} is not below {g}, which was too high"),
+                (Some(a), Ok(g), Verdict::TooLow) if a <= g => bail!("{answer} is not above {g}, which was too low"),
+                _ => {},
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn record(&mut self, guess: Guess) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{guess}")?;
+        self.guesses.push(guess);
+
+        Ok(())
+    }
+}
+
+pub fn submit(client: &Client, log: &mut Log, day: u32, part: u32, answer: &str) -> Result<Verdict> {
+    if let Some(verdict) = log.check(day, part, answer)? {
+        return Ok(verdict);
+    }
+
+    let response = client.post(
+        &format!("/{YEAR}/day/{day}/answer"),
+        &[("level", &part.to_string()), ("answer", answer)],
+    )?;
+    let verdict = parse_verdict(&response)?;
+
+    log.record(Guess {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        day,
+        part,
+        answer: answer.to_string(),
+        verdict,
+    })?;
+
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::stub;
+
+    const TOO_HIGH: &str = "<article><p>That's not the right answer; your answer is too high.</p></article>";
+    const CORRECT: &str = "<article><p>That's the right answer! You are one gold star closer.</p></article>";
+    const RATE_LIMITED: &str = "<article><p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 5s left to wait.</p></article>";
+
+    fn temp_log(name: &str) -> Log {
+        let path = std::env::temp_dir().join(format!("aoc-2023-{name}-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        Log::load(&path).unwrap()
+    }
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(Verdict::TooHigh, parse_verdict(TOO_HIGH).unwrap());
+        assert_eq!(Verdict::Correct, parse_verdict(CORRECT).unwrap());
+        assert_eq!(Verdict::RateLimited(Duration::from_secs(65)), parse_verdict(RATE_LIMITED).unwrap());
+        assert!(parse_verdict("<html></html>").is_err());
+
+        assert_eq!(Duration::from_secs(2 * 60 * 60 + 3), parse_wait("2h 3s").unwrap());
+        assert!(parse_wait("5é").is_err());
+        assert!(parse_wait("é").is_err());
+    }
+
+    #[test]
+    fn submit_and_log() {
+        let (base_url, requests) = stub::serve(vec![(200, TOO_HIGH), (200, CORRECT)]);
+        let client = Client::new(&base_url, "secret").with_min_request_interval(Duration::ZERO);
+        let mut log = temp_log("submit");
+
+        assert_eq!(Verdict::TooHigh, submit(&client, &mut log, 1, 2, "500").unwrap());
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /2023/day/1/answer "));
+        assert!(request.ends_with("level=2&answer=500"));
+
+        assert!(submit(&client, &mut log, 1, 2, "500").is_err());
+        assert!(submit(&client, &mut log, 1, 2, "501").is_err());
+
+        assert_eq!(Verdict::Correct, submit(&client, &mut log, 1, 2, "499").unwrap());
+        assert_eq!(Verdict::Correct, submit(&client, &mut log, 1, 2, "499").unwrap());
+        assert!(submit(&client, &mut log, 1, 2, "498").is_err());
+
+        let reloaded = Log::load(&log.path).unwrap();
+        assert_eq!(log.guesses, reloaded.guesses);
+        fs::remove_file(&log.path).unwrap();
+    }
+}